@@ -0,0 +1,25 @@
+// Writes the hand ranker tables into OUT_DIR for `HandRanker::embedded`.
+
+#[allow(dead_code)]
+#[path = "src/table_gen.rs"]
+mod table_gen;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/table_gen.rs");
+
+    if std::env::var_os("CARGO_FEATURE_EMBEDDED").is_none() {
+        return;
+    }
+
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let tables = table_gen::generate(table_gen::Ranking::Standard);
+    for (name, table) in [
+        ("offsuited5", &tables.offsuited5),
+        ("suited", &tables.suited),
+    ] {
+        let filename = format!("{}/{}.bin", out_dir, name);
+        std::fs::write(&filename, table_gen::encode_table(table))
+            .unwrap_or_else(|e| panic!("failed to write {:?}: {}", filename, e));
+    }
+}
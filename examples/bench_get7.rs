@@ -0,0 +1,38 @@
+use hardcore_equitizer::{Card, HandRanker};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::time::Instant;
+
+/// Usage: cargo run --release --example bench_get7 [num_hands]
+fn main() {
+    let num_hands = std::env::args()
+        .nth(1)
+        .map_or(1_000_000, |arg| arg.parse::<usize>().unwrap());
+
+    let start = Instant::now();
+    let hand_ranker = HandRanker::generate();
+    eprintln!("generate: {:?}", start.elapsed());
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut deck = (0..52).map(Card::from_value).collect::<Vec<_>>();
+    let hands = (0..num_hands)
+        .map(|_| {
+            let (cards, _) = deck.partial_shuffle(&mut rng, 7);
+            [0, 1, 2, 3, 4, 5, 6].map(|i| cards[i])
+        })
+        .collect::<Vec<_>>();
+
+    for _ in 0..3 {
+        let start = Instant::now();
+        let checksum = hands.iter().fold(0_i64, |acc, &hand| {
+            acc + hand_ranker.get7(hand).value() as i64
+        });
+        let elapsed = start.elapsed();
+        eprintln!(
+            "get7: {:.1} ns/hand (checksum {})",
+            elapsed.as_nanos() as f64 / num_hands as f64,
+            checksum
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use hardcore_equitizer::{Equitizer, HandRanker, MemoryCache, PreflopTable};
+
+/// Usage: cargo run --release --example gen_preflop_table [data_dir] [output]
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let data_dir = args.next().unwrap_or("data".to_string());
+    let filename = args
+        .next()
+        .unwrap_or(PreflopTable::DEFAULT_FILENAME.to_string());
+
+    let hand_ranker = HandRanker::new(&data_dir).unwrap();
+    let equitizer = Equitizer::with_cache(&hand_ranker, Box::new(MemoryCache::new()));
+
+    let table = PreflopTable::generate(&equitizer, |done, total| {
+        if done % 1000 == 0 || done == total {
+            eprintln!("{}/{}", done, total);
+        }
+    });
+
+    table.save_to(&filename).unwrap();
+}
@@ -0,0 +1,172 @@
+use super::types::Card;
+use permutohedron::LexicalPermutation;
+
+/// Relabelling of the four suits: a card of suit value `s` becomes one of suit value `suits[s]`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SuitPermutation {
+    suits: [i32; 4],
+}
+
+impl SuitPermutation {
+    pub const IDENTITY: Self = Self {
+        suits: [0, 1, 2, 3],
+    };
+
+    /// All 24 permutations, starting with the identity.
+    pub fn all() -> Vec<Self> {
+        let mut suits = [0, 1, 2, 3];
+        let mut permutations = Vec::with_capacity(24);
+
+        loop {
+            permutations.push(Self { suits });
+            if !suits.next_permutation() {
+                break;
+            }
+        }
+
+        permutations
+    }
+
+    pub fn apply(&self, card: Card) -> Card {
+        Card::from_rank_suit_value(card.rank().value, self.suits[card.suit().as_usize()])
+    }
+
+    pub fn apply_all(&self, cards: &[Card]) -> Vec<Card> {
+        cards.iter().map(|&card| self.apply(card)).collect()
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut suits = [0; 4];
+        for (from, &to) in self.suits.iter().enumerate() {
+            suits[to as usize] = from as i32;
+        }
+        Self { suits }
+    }
+}
+
+/// Suit-canonical form of a deal: every player's hole cards, the board and the dead cards.
+///
+/// The cards are kept in the order cache files hold them in: hole cards in descending order,
+/// like [`crate::Combo::new`] does, board and dead cards in ascending order.
+#[derive(Clone, Debug)]
+pub struct CanonicalCards {
+    /// The hole cards of each player, in their original order.
+    pub hands: Vec<Vec<Card>>,
+    pub board: Vec<Card>,
+    pub dead: Vec<Card>,
+    /// Maps the original cards onto the canonical ones.
+    pub permutation: SuitPermutation,
+}
+
+impl CanonicalCards {
+    /// Maps cards of the canonical form back onto the original suits.
+    pub fn to_original(&self, cards: &[Card]) -> Vec<Card> {
+        self.permutation.inverse().apply_all(cards)
+    }
+
+    /// The canonical cards without the permutation, equal for every suit-isomorphic deal.
+    pub fn into_key(self) -> (Vec<Vec<Card>>, Vec<Card>, Vec<Card>) {
+        (self.hands, self.board, self.dead)
+    }
+}
+
+/// Picks the smallest relabelling of the deal over all suit permutations, comparing the hands
+/// first, then the board, then the dead cards. Two deals get the same canonical cards exactly
+/// when one is a suit permutation of the other. The order of the players matters, the order of
+/// the cards within a hand, the board or the dead cards does not.
+pub fn canonicalize(hands: &[&[Card]], board: &[Card], dead: &[Card]) -> CanonicalCards {
+    let mut best: Option<CanonicalCards> = None;
+
+    for permutation in SuitPermutation::all() {
+        let ascending = |cards: &[Card]| {
+            let mut cards = permutation.apply_all(cards);
+            cards.sort();
+            cards
+        };
+        let permuted = CanonicalCards {
+            hands: hands
+                .iter()
+                .map(|cards| {
+                    let mut cards = ascending(cards);
+                    cards.reverse();
+                    cards
+                })
+                .collect(),
+            board: ascending(board),
+            dead: ascending(dead),
+            permutation,
+        };
+
+        let is_smaller = |best: &CanonicalCards| {
+            (&permuted.hands, &permuted.board, &permuted.dead)
+                < (&best.hands, &best.board, &best.dead)
+        };
+        if best.as_ref().is_none_or(is_smaller) {
+            best = Some(permuted);
+        }
+    }
+
+    best.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(abbr: &str) -> Vec<Card> {
+        (0..abbr.len())
+            .step_by(2)
+            .map(|i| Card::parse(&abbr[i..i + 2]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let canonical = canonicalize(&[&cards("AsKs"), &cards("QdQc")], &cards("Td9s2c"), &[]);
+        let isomorphic = canonicalize(&[&cards("KhAh"), &cards("QsQd")], &cards("2dTs9h"), &[]);
+        assert_eq!(canonical.clone().into_key(), isomorphic.into_key());
+
+        // hole cards descending, board ascending
+        assert_eq!(canonical.hands, vec![cards("AcKc"), cards("QhQd")]);
+        assert_eq!(canonical.board, cards("2d9cTh"));
+        assert_eq!(canonical.to_original(&canonical.board), cards("2c9sTd"));
+
+        // the same cards in a different role
+        let swapped = canonicalize(&[&cards("QdQc"), &cards("AsKs")], &cards("Td9s2c"), &[]);
+        assert_ne!(canonical.clone().into_key(), swapped.into_key());
+        let dead = canonicalize(
+            &[&cards("AsKs"), &cards("QdQc")],
+            &cards("Td9s"),
+            &cards("2c"),
+        );
+        assert_ne!(canonical.clone().into_key(), dead.into_key());
+
+        let different = canonicalize(&[&cards("AsKs"), &cards("QdQc")], &cards("Td9d2c"), &[]);
+        assert_ne!(canonical.into_key(), different.into_key());
+
+        let omaha = canonicalize(&[&cards("AsKsQhJh"), &cards("9c8c7d6d")], &[], &[]);
+        assert_eq!(
+            omaha.to_original(&omaha.hands[0]),
+            cards("AsKsQhJh"),
+            "{:?}",
+            omaha
+        );
+    }
+
+    #[test]
+    fn test_suit_permutation() {
+        let permutations = SuitPermutation::all();
+        assert_eq!(permutations.len(), 24);
+        assert_eq!(permutations[0], SuitPermutation::IDENTITY);
+
+        let deck = (0..52).map(Card::from_value).collect::<Vec<_>>();
+        for permutation in permutations {
+            assert_eq!(
+                permutation
+                    .inverse()
+                    .apply_all(&permutation.apply_all(&deck)),
+                deck
+            );
+        }
+    }
+}
@@ -1,258 +1,978 @@
 use crate::types::Suit;
 
+use super::canonicalizer::canonicalize;
+use super::equity_cache::AppendFileCache;
+use super::equity_cache::EquityCache;
+use super::equity_cache::EquityKey;
 use super::hand_ranker::HandRanker;
+use super::low_ranker::LowRanker;
+use super::preflop_table::PreflopTable;
 use super::types::Card;
 use super::types::Combo;
+use super::types::ComboEquity;
+use super::types::EquityResult;
+use super::types::HandRank;
+use super::types::HiLoEquityResult;
+use super::types::LowRank;
+use super::types::MonteCarloConfig;
+use super::types::MonteCarloResult;
+use super::types::OmahaHand;
+use super::types::OmahaRange;
+use super::types::PartialHand;
 use super::types::PureRange;
 use super::types::Range;
 use super::types::StackedError;
+use super::types::WeightedCombo;
+use crate::format_stacked_err;
+use rand::Rng;
 use std::collections::HashMap;
-use std::io::{BufRead, Write};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Answers equity queries through `&self`, so one instance can be shared across threads.
 pub struct Equitizer<'a> {
     hand_ranker: &'a HandRanker,
-    cache: HashMap<(Card, Card, Card, Card), f64>,
+    /// Built on the first hi-lo query.
+    low_ranker: OnceLock<LowRanker>,
+    cache: Box<dyn EquityCache>,
+    preflop_table: Option<PreflopTable>,
+    num_threads: usize,
 }
 
 impl<'a> Equitizer<'a> {
-    const CACHE_FILENAME: &'static str = "data/equitizer_cache.txt";
+    pub const MAX_MULTIWAY_EVALUATIONS: f64 = 1e10;
+    const MAX_DEAL_REJECTIONS: usize = 10_000;
 
+    /// Appends new results to [`AppendFileCache::DEFAULT_FILENAME`] on [`Equitizer::flush`] and
+    /// answers preflop queries from [`PreflopTable::embedded`]. A short-deck `hand_ranker`
+    /// appends to [`AppendFileCache::SHORT_DECK_FILENAME`] instead and has no preflop table.
     pub fn new(hand_ranker: &'a HandRanker) -> Result<Self, StackedError> {
-        let mut cache = HashMap::new();
-
-        match std::fs::File::open(Self::CACHE_FILENAME) {
-            Ok(file) => {
-                for line in std::io::BufReader::new(file).lines() {
-                    let line = line.unwrap();
-                    let parts = line.split_whitespace().collect::<Vec<&str>>();
-                    if parts.len() != 3 {
-                        return Err(StackedError::new(format!(
-                            "Invalid line in cache file: {}",
-                            line
-                        )));
-                    }
-                    let h1 = parts[0];
-                    if h1.len() != 4 {
-                        return Err(StackedError::new(format!(
-                            "Invalid line in cache file: {}",
-                            line
-                        )));
-                    }
-                    let c1 = Card::parse(&h1[0..2]).unwrap();
-                    let c2 = Card::parse(&h1[2..4]).unwrap();
-                    let h2 = parts[1];
-                    if h2.len() != 4 {
-                        panic!("Invalid line in cache file: {}", line);
-                    }
-                    let c3 = Card::parse(&h2[0..2]).unwrap();
-                    let c4 = Card::parse(&h2[2..4]).unwrap();
-                    let equity = parts[2].parse::<f64>().unwrap();
-                    cache.insert((c1, c2, c3, c4), equity);
+        let filename = if hand_ranker.is_short_deck() {
+            AppendFileCache::SHORT_DECK_FILENAME
+        } else {
+            AppendFileCache::DEFAULT_FILENAME
+        };
+        let cache =
+            AppendFileCache::open(filename).map_err(|e| e.chain("Equitizer::new".to_string()))?;
+        let mut equitizer = Self::with_cache(hand_ranker, Box::new(cache));
+
+        if !hand_ranker.is_short_deck() {
+            equitizer.set_preflop_table(PreflopTable::embedded());
+        }
+
+        Ok(equitizer)
+    }
+
+    /// Uses `cache` for every result and does no IO of its own. Runs on as many threads as
+    /// the machine offers until [`Equitizer::set_num_threads`] says otherwise.
+    pub fn with_cache(hand_ranker: &'a HandRanker, cache: Box<dyn EquityCache>) -> Self {
+        Self {
+            hand_ranker,
+            low_ranker: OnceLock::new(),
+            cache,
+            preflop_table: None,
+            num_threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+
+    /// Persists the results computed since the last flush, see [`EquityCache::flush`].
+    pub fn flush(&self) -> Result<(), StackedError> {
+        self.cache.flush()
+    }
+
+    /// Serves preflop [`Equitizer::hand_vs_hand`] queries from `table` before the cache.
+    pub fn set_preflop_table(&mut self, table: PreflopTable) {
+        self.preflop_table = Some(table);
+    }
+
+    pub fn num_threads(&self) -> usize {
+        self.num_threads
+    }
+
+    /// Number of threads a single range or runout enumeration is split across, at least 1.
+    pub fn set_num_threads(&mut self, num_threads: usize) {
+        self.num_threads = num_threads.max(1);
+    }
+
+    pub fn range_vs_range(&self, lhs: &impl Range, rhs: &impl Range) -> EquityResult {
+        self.range_vs_range_on_board(lhs, rhs, &[])
+    }
+
+    /// Equity of `lhs` against `rhs` on a fixed flop, turn or river.
+    /// Combos that use a board card are dropped from both ranges.
+    pub fn range_vs_range_on_board(
+        &self,
+        lhs: &impl Range,
+        rhs: &impl Range,
+        board: &[Card],
+    ) -> EquityResult {
+        self.range_vs_range_with_dead(lhs, rhs, board, &[])
+    }
+
+    /// Like [`Equitizer::range_vs_range_on_board`], but `dead` cards are also removed from the
+    /// deck and from both ranges. The remaining combo weights are renormalised.
+    pub fn range_vs_range_with_dead(
+        &self,
+        lhs: &impl Range,
+        rhs: &impl Range,
+        board: &[Card],
+        dead: &[Card],
+    ) -> EquityResult {
+        let mut res = EquityResult::default();
+
+        for combo_equity in self.range_vs_range_by_combo(lhs, rhs, board, dead) {
+            res.add_weighted(&combo_equity.result, combo_equity.weight);
+        }
+
+        res
+    }
+
+    /// Breaks [`Equitizer::range_vs_range_with_dead`] down by hero combo.
+    ///
+    /// Every live combo of `lhs` gets its result against the part of `rhs` it does not block.
+    /// Combos that use a board or dead card, or that block all of `rhs`, are left out.
+    /// The hero combos are split across [`Equitizer::num_threads`] threads.
+    pub fn range_vs_range_by_combo(
+        &self,
+        lhs: &impl Range,
+        rhs: &impl Range,
+        board: &[Card],
+        dead: &[Card],
+    ) -> Vec<ComboEquity> {
+        let lhs_weighted_combos = live_combos(self.hand_ranker, lhs, board, dead);
+        let rhs_weighted_combos = live_combos(self.hand_ranker, rhs, board, dead);
+
+        let chunk_size = lhs_weighted_combos.len().div_ceil(self.num_threads).max(1);
+        let calc_chunk = |chunk: &[WeightedCombo]| {
+            chunk
+                .iter()
+                .filter_map(|lhs_weighted_combo| {
+                    self.combo_vs_range(lhs_weighted_combo, &rhs_weighted_combos, board, dead)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        if self.num_threads == 1 {
+            return calc_chunk(&lhs_weighted_combos);
+        }
+
+        std::thread::scope(|scope| {
+            let handles = lhs_weighted_combos
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || calc_chunk(chunk)))
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+
+    /// Takes the matchups against `rhs_weighted_combos` from the cache where possible and
+    /// enumerates the others together, see [`Equitizer::enumerate_runouts_vs_many`].
+    fn combo_vs_range(
+        &self,
+        lhs_weighted_combo: &WeightedCombo,
+        rhs_weighted_combos: &[WeightedCombo],
+        board: &[Card],
+        dead: &[Card],
+    ) -> Option<ComboEquity> {
+        let lhs = lhs_weighted_combo.combo;
+        let mut matchups = Vec::new();
+        let mut misses = Vec::new();
+        for rhs_weighted_combo in rhs_weighted_combos {
+            if lhs.intersects(&rhs_weighted_combo.combo) {
+                continue;
+            }
+
+            let matchup_res = match self.lookup(lhs, rhs_weighted_combo.combo, board, dead) {
+                Lookup::Hit(res) => res,
+                Lookup::Miss(key) => {
+                    misses.push((matchups.len(), key));
+                    EquityResult::default()
                 }
+            };
+            matchups.push((matchup_res, rhs_weighted_combo));
+        }
+
+        if !misses.is_empty() {
+            // isomorphic matchups share a key and are enumerated once
+            let mut unique_keys = HashMap::new();
+            let mut villains = Vec::new();
+            for (i, key) in &misses {
+                unique_keys.entry(key).or_insert_with(|| {
+                    villains.push(matchups[*i].1.combo);
+                    villains.len() - 1
+                });
             }
-            Err(_) => {
-                println!(
-                    "failed to open cache file: {:?}, no action required",
-                    Self::CACHE_FILENAME
+            let results = self.enumerate_runouts_vs_many(lhs, &villains, board, dead);
+
+            for (i, key) in &misses {
+                matchups[*i].0 = results[unique_keys[key]];
+            }
+            for (key, j) in unique_keys {
+                self.cache.insert(key.clone(), results[j]);
+            }
+        }
+
+        let mut res = EquityResult::default();
+        for (matchup_res, rhs_weighted_combo) in matchups {
+            res.add_weighted(&matchup_res, rhs_weighted_combo.weight);
+        }
+
+        if res.total > 0.0 {
+            Some(ComboEquity {
+                combo: lhs,
+                weight: lhs_weighted_combo.weight * res.total,
+                result: res,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Equity of `hero` against `villain` with `board` (0, 3, 4 or 5 cards) already dealt.
+    ///
+    /// Preflop queries go through [`Equitizer::hand_vs_hand`], the others are cached under the
+    /// suit-canonicalised (hero, villain, board, dead) cards.
+    pub fn hand_vs_hand_on_board(
+        &self,
+        hero: Combo,
+        villain: Combo,
+        board: &[Card],
+    ) -> EquityResult {
+        self.hand_vs_hand_with_dead(hero, villain, board, &[])
+    }
+
+    /// Equity of `hero` against `villain` with `board` dealt and `dead` cards out of the deck.
+    pub fn hand_vs_hand_with_dead(
+        &self,
+        hero: Combo,
+        villain: Combo,
+        board: &[Card],
+        dead: &[Card],
+    ) -> EquityResult {
+        self.cached_hand_vs_hand(hero, villain, board, dead, self.num_threads)
+    }
+
+    pub fn hand_vs_hand(&self, hero: Combo, villain: Combo) -> EquityResult {
+        self.cached_hand_vs_hand(hero, villain, &[], &[], self.num_threads)
+    }
+
+    fn cached_hand_vs_hand(
+        &self,
+        hero: Combo,
+        villain: Combo,
+        board: &[Card],
+        dead: &[Card],
+        num_threads: usize,
+    ) -> EquityResult {
+        match self.lookup(hero, villain, board, dead) {
+            Lookup::Hit(res) => res,
+            Lookup::Miss(key) => {
+                // the key's cards are suit-isomorphic to the query's, so they have the same
+                // runouts
+                let res = self.enumerate_runouts(
+                    key.hero,
+                    key.villain,
+                    &key.board,
+                    &key.dead,
+                    num_threads,
                 );
+                self.cache.insert(key, res);
+                res
             }
         }
+    }
+
+    /// Looks the matchup up in the preflop table and the cache.
+    fn lookup(&self, hero: Combo, villain: Combo, board: &[Card], dead: &[Card]) -> Lookup {
+        check_known_cards(
+            self.hand_ranker,
+            &[hero.0, hero.1, villain.0, villain.1],
+            board,
+            dead,
+        );
+
+        let key = if board.is_empty() && dead.is_empty() {
+            let (hero, villain) = normalize_preflop(hero, villain);
+
+            if let Some(res) = self
+                .preflop_table
+                .as_ref()
+                .and_then(|table| table.get(&(hero.0, hero.1, villain.0, villain.1)))
+            {
+                return Lookup::Hit(res);
+            }
+
+            EquityKey {
+                hero,
+                villain,
+                board: Vec::new(),
+                dead: Vec::new(),
+            }
+        } else {
+            canonical_key(hero, villain, board, dead)
+        };
 
-        Ok(Self { hand_ranker, cache })
+        match self.cache.get(&key) {
+            Some(res) => Lookup::Hit(res),
+            None => Lookup::Miss(key),
+        }
     }
 
-    pub fn range_vs_range(&mut self, lhs: &impl Range, rhs: &impl Range) -> f64 {
-        let mut sum_eq = 0.0;
-        let mut sum_weights = 0.0;
+    pub fn calc_combo_vs_combo(&self, lhs: Combo, rhs: Combo) -> EquityResult {
+        self.calc_combo_vs_combo_on_board(lhs, rhs, &[])
+    }
 
-        for lhs_weighted_combo in lhs.iter_weighted_combos() {
-            for rhs_weighted_combo in rhs.iter_weighted_combos() {
-                if lhs_weighted_combo
-                    .combo
-                    .intersects(&rhs_weighted_combo.combo)
-                {
+    /// Enumerates every runout that completes `board` to five cards.
+    pub fn calc_combo_vs_combo_on_board(
+        &self,
+        lhs: Combo,
+        rhs: Combo,
+        board: &[Card],
+    ) -> EquityResult {
+        self.calc_combo_vs_combo_with_dead(lhs, rhs, board, &[])
+    }
+
+    /// Enumerates every runout that completes `board` to five cards without using `dead` cards,
+    /// split across [`Equitizer::num_threads`] threads.
+    pub fn calc_combo_vs_combo_with_dead(
+        &self,
+        lhs: Combo,
+        rhs: Combo,
+        board: &[Card],
+        dead: &[Card],
+    ) -> EquityResult {
+        check_known_cards(self.hand_ranker, &[lhs.0, lhs.1, rhs.0, rhs.1], board, dead);
+        self.enumerate_runouts(lhs, rhs, board, dead, self.num_threads)
+    }
+
+    fn enumerate_runouts(
+        &self,
+        lhs: Combo,
+        rhs: Combo,
+        board: &[Card],
+        dead: &[Card],
+        num_threads: usize,
+    ) -> EquityResult {
+        let deck = self
+            .hand_ranker
+            .deck()
+            .into_iter()
+            .filter(|&c| {
+                !lhs.contains(c) && !rhs.contains(c) && !board.contains(&c) && !dead.contains(&c)
+            })
+            .collect::<Vec<_>>();
+
+        let lhs_hand = PartialHand::from_cards(&[lhs.0, lhs.1]);
+        let rhs_hand = PartialHand::from_cards(&[rhs.0, rhs.1]);
+        let board_hand = PartialHand::from_cards(board);
+
+        let counts = count_runouts(&deck, board, num_threads, |full_board, counts| {
+            let runout = PartialHand::from_cards(&full_board[board.len()..]).union(board_hand);
+            let hero_hand_rank = self.hand_ranker.evaluate_partial(&lhs_hand.union(runout));
+            let villain_hand_rank = self.hand_ranker.evaluate_partial(&rhs_hand.union(runout));
+            count_showdown(hero_hand_rank, villain_hand_rank, counts);
+        });
+
+        EquityResult::new(counts[0], counts[1], counts[2])
+    }
+
+    /// Results of `hero` against each of `villains`, from one pass over the runouts of the
+    /// deck without the hero, board and dead cards. Every runout is ranked for all villains at
+    /// once with [`HandRanker::evaluate_board_batch`]; a villain only counts the runouts that
+    /// miss its cards, which are exactly the runouts of its own matchup.
+    fn enumerate_runouts_vs_many(
+        &self,
+        hero: Combo,
+        villains: &[Combo],
+        board: &[Card],
+        dead: &[Card],
+    ) -> Vec<EquityResult> {
+        let deck = self
+            .hand_ranker
+            .deck()
+            .into_iter()
+            .filter(|&c| !hero.contains(c) && !board.contains(&c) && !dead.contains(&c))
+            .collect::<Vec<_>>();
+
+        let hero_hand = PartialHand::from_cards(&[hero.0, hero.1]);
+        let board_hand = PartialHand::from_cards(board);
+        let villain_hands = villains
+            .iter()
+            .map(|villain| PartialHand::from_cards(&[villain.0, villain.1]))
+            .collect::<Vec<_>>();
+
+        let mut counts = vec![[0_u64; 3]; villains.len()];
+        let mut villain_hand_ranks = vec![HandRank::ERROR; villains.len()];
+        let mut full_board = [Card::from_value(0); 5];
+        full_board[..board.len()].copy_from_slice(board);
+
+        let mut showdown = |full_board: &[Card; 5]| {
+            let runout = PartialHand::from_cards(&full_board[board.len()..]).union(board_hand);
+            let hero_hand_rank = self.hand_ranker.evaluate_partial(&hero_hand.union(runout));
+            self.hand_ranker
+                .evaluate_board_batch(&runout, &villain_hands, &mut villain_hand_ranks);
+
+            for (counts, &villain_hand_rank) in counts.iter_mut().zip(&villain_hand_ranks) {
+                if villain_hand_rank == HandRank::ERROR {
                     continue;
                 }
 
-                let weight = lhs_weighted_combo.weight * rhs_weighted_combo.weight;
-                sum_eq +=
-                    self.hand_vs_hand(lhs_weighted_combo.combo, rhs_weighted_combo.combo) * weight;
-                sum_weights += weight;
+                count_showdown(hero_hand_rank, villain_hand_rank, counts);
             }
-        }
+        };
+        for_each_runout(&deck, 0, &mut full_board, board.len(), &mut showdown);
 
-        sum_eq / sum_weights
+        counts
+            .into_iter()
+            .map(|[wins, ties, losses]| EquityResult::new(wins, ties, losses))
+            .collect()
     }
 
-    pub fn hand_vs_hand(&mut self, mut hero: Combo, mut villain: Combo) -> f64 {
-        if hero.0.suit() == hero.1.suit() {
-            let hero_suit = hero.0.suit();
-            if villain.0.suit() == villain.1.suit() {
-                if hero.0.suit() == villain.0.suit() {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::CLUB);
-                    villain.0 = villain.0.with_suit(&Suit::CLUB);
-                    villain.1 = villain.1.with_suit(&Suit::CLUB);
-                } else {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::CLUB);
-                    villain.0 = villain.0.with_suit(&Suit::DIAMOND);
-                    villain.1 = villain.1.with_suit(&Suit::DIAMOND);
-                }
-            } else {
-                if hero_suit == villain.0.suit() {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::CLUB);
-                    villain.0 = villain.0.with_suit(&Suit::CLUB);
-                    villain.1 = villain.1.with_suit(&Suit::DIAMOND);
-                } else if hero_suit == villain.1.suit() {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::CLUB);
-                    villain.0 = villain.0.with_suit(&Suit::DIAMOND);
-                    villain.1 = villain.1.with_suit(&Suit::CLUB);
-                } else {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::CLUB);
-                    villain.0 = villain.0.with_suit(&Suit::DIAMOND);
-                    villain.1 = villain.1.with_suit(&Suit::HEART);
+    /// Omaha equity of `hero` against `villain` with `board` dealt and `dead` cards out of the
+    /// deck, enumerated across [`Equitizer::num_threads`] threads. Not cached.
+    pub fn omaha_hand_vs_hand(
+        &self,
+        hero: &OmahaHand,
+        villain: &OmahaHand,
+        board: &[Card],
+        dead: &[Card],
+    ) -> EquityResult {
+        check_known_cards(
+            self.hand_ranker,
+            &[hero.cards(), villain.cards()].concat(),
+            board,
+            dead,
+        );
+        self.enumerate_omaha_runouts(hero, villain, board, dead, self.num_threads)
+    }
+
+    /// Omaha equity of `lhs` against `rhs`, every pair of hands without common cards counting
+    /// the same. Hands that use a board or dead card or a card outside the deck are left out; the
+    /// `lhs` hands are split across [`Equitizer::num_threads`] threads.
+    ///
+    /// Fails when the board is not 0, 3, 4 or 5 cards long, or when a board or dead card is
+    /// outside the deck or dealt twice.
+    pub fn omaha_range_vs_range(
+        &self,
+        lhs: &OmahaRange,
+        rhs: &OmahaRange,
+        board: &[Card],
+        dead: &[Card],
+    ) -> Result<EquityResult, StackedError> {
+        check_board_and_dead(self.hand_ranker, board, dead)?;
+
+        let is_live = |hand: &&OmahaHand| {
+            hand.cards()
+                .iter()
+                .all(|&card| self.hand_ranker.in_deck(card))
+                && !hand.intersects_cards(board)
+                && !hand.intersects_cards(dead)
+        };
+        let lhs_hands = lhs.hands.iter().filter(is_live).collect::<Vec<_>>();
+        let rhs_hands = rhs.hands.iter().filter(is_live).collect::<Vec<_>>();
+
+        let calc_chunk = |chunk: &[&OmahaHand]| {
+            let mut res = EquityResult::default();
+            // suit-isomorphic matchups have the same result
+            let mut results_by_matchup = HashMap::new();
+            for lhs_hand in chunk {
+                for rhs_hand in rhs_hands.iter().filter(|h| !h.intersects(lhs_hand)) {
+                    let canonical =
+                        canonicalize(&[lhs_hand.cards(), rhs_hand.cards()], board, dead);
+                    let matchup_res = results_by_matchup
+                        .entry(canonical.into_key())
+                        .or_insert_with(|| {
+                            // already running on one thread per hero hand
+                            self.enumerate_omaha_runouts(lhs_hand, rhs_hand, board, dead, 1)
+                        });
+                    res.add_weighted(matchup_res, 1.0);
                 }
             }
-        } else {
-            if villain.0.suit() == villain.1.suit() {
-                let villain_suit = villain.0.suit();
-                if hero.0.suit() == villain_suit {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::DIAMOND);
-                    villain.0 = villain.0.with_suit(&Suit::CLUB);
-                    villain.1 = villain.1.with_suit(&Suit::CLUB);
-                } else if hero.1.suit() == villain_suit {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::DIAMOND);
-                    villain.0 = villain.0.with_suit(&Suit::DIAMOND);
-                    villain.1 = villain.1.with_suit(&Suit::DIAMOND);
-                } else {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::DIAMOND);
-                    villain.0 = villain.0.with_suit(&Suit::HEART);
-                    villain.1 = villain.1.with_suit(&Suit::HEART);
-                }
-            } else {
-                if hero.0.suit() == villain.0.suit() {
-                    if hero.1.suit() == villain.1.suit() {
-                        hero.0 = hero.0.with_suit(&Suit::CLUB);
-                        hero.1 = hero.1.with_suit(&Suit::DIAMOND);
-                        villain.0 = villain.0.with_suit(&Suit::CLUB);
-                        villain.1 = villain.1.with_suit(&Suit::DIAMOND);
-                    } else {
-                        hero.0 = hero.0.with_suit(&Suit::CLUB);
-                        hero.1 = hero.1.with_suit(&Suit::DIAMOND);
-                        villain.0 = villain.0.with_suit(&Suit::CLUB);
-                        villain.1 = villain.1.with_suit(&Suit::HEART);
-                    }
-                } else if hero.0.suit() == villain.1.suit() {
-                    if hero.1.suit() == villain.0.suit() {
-                        hero.0 = hero.0.with_suit(&Suit::CLUB);
-                        hero.1 = hero.1.with_suit(&Suit::DIAMOND);
-                        villain.0 = villain.0.with_suit(&Suit::DIAMOND);
-                        villain.1 = villain.1.with_suit(&Suit::CLUB);
-                    } else {
-                        hero.0 = hero.0.with_suit(&Suit::CLUB);
-                        hero.1 = hero.1.with_suit(&Suit::DIAMOND);
-                        villain.0 = villain.0.with_suit(&Suit::HEART);
-                        villain.1 = villain.1.with_suit(&Suit::CLUB);
-                    }
-                } else if hero.1.suit() == villain.0.suit() {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::DIAMOND);
-                    villain.0 = villain.0.with_suit(&Suit::DIAMOND);
-                    villain.1 = villain.1.with_suit(&Suit::HEART);
-                } else if hero.1.suit() == villain.1.suit() {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::DIAMOND);
-                    villain.0 = villain.0.with_suit(&Suit::HEART);
-                    villain.1 = villain.1.with_suit(&Suit::DIAMOND);
-                } else {
-                    hero.0 = hero.0.with_suit(&Suit::CLUB);
-                    hero.1 = hero.1.with_suit(&Suit::DIAMOND);
-                    villain.0 = villain.0.with_suit(&Suit::HEART);
-                    villain.1 = villain.1.with_suit(&Suit::SPADE);
+            res
+        };
+
+        if self.num_threads == 1 {
+            return Ok(calc_chunk(&lhs_hands));
+        }
+
+        let chunk_size = lhs_hands.len().div_ceil(self.num_threads).max(1);
+        let res = std::thread::scope(|scope| {
+            let handles = lhs_hands
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || calc_chunk(chunk)))
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .fold(EquityResult::default(), |mut res, handle| {
+                    let chunk_res = handle.join().unwrap();
+                    res.wins += chunk_res.wins;
+                    res.ties += chunk_res.ties;
+                    res.losses += chunk_res.losses;
+                    res.total += chunk_res.total;
+                    res
+                })
+        });
+
+        Ok(res)
+    }
+
+    fn enumerate_omaha_runouts(
+        &self,
+        hero: &OmahaHand,
+        villain: &OmahaHand,
+        board: &[Card],
+        dead: &[Card],
+        num_threads: usize,
+    ) -> EquityResult {
+        let deck = self
+            .hand_ranker
+            .deck()
+            .into_iter()
+            .filter(|&c| {
+                !hero.contains(c)
+                    && !villain.contains(c)
+                    && !board.contains(&c)
+                    && !dead.contains(&c)
+            })
+            .collect::<Vec<_>>();
+
+        let counts = count_runouts(&deck, board, num_threads, |full_board, counts| {
+            let hero_hand_rank = self.hand_ranker.get_omaha(hero.cards(), *full_board);
+            let villain_hand_rank = self.hand_ranker.get_omaha(villain.cards(), *full_board);
+            count_showdown(hero_hand_rank, villain_hand_rank, counts);
+        });
+
+        EquityResult::new(counts[0], counts[1], counts[2])
+    }
+
+    /// Omaha-8 (Omaha hi-lo, eight or better) result of `hero` against `villain` with `board`
+    /// dealt and `dead` cards out of the deck, enumerated across [`Equitizer::num_threads`]
+    /// threads. Both halves use exactly two hole cards and three board cards. Not cached.
+    pub fn omaha_hi_lo_hand_vs_hand(
+        &self,
+        hero: &OmahaHand,
+        villain: &OmahaHand,
+        board: &[Card],
+        dead: &[Card],
+    ) -> HiLoEquityResult {
+        check_known_cards(
+            self.hand_ranker,
+            &[hero.cards(), villain.cards()].concat(),
+            board,
+            dead,
+        );
+        let deck = self
+            .hand_ranker
+            .deck()
+            .into_iter()
+            .filter(|&c| {
+                !hero.contains(c)
+                    && !villain.contains(c)
+                    && !board.contains(&c)
+                    && !dead.contains(&c)
+            })
+            .collect::<Vec<_>>();
+        let low_ranker = self.low_ranker();
+
+        let counts = count_runouts(&deck, board, self.num_threads, |full_board, counts| {
+            count_hi_lo_showdown(
+                (
+                    self.hand_ranker.get_omaha(hero.cards(), *full_board),
+                    low_ranker.omaha_eight_or_better(hero.cards(), *full_board),
+                ),
+                (
+                    self.hand_ranker.get_omaha(villain.cards(), *full_board),
+                    low_ranker.omaha_eight_or_better(villain.cards(), *full_board),
+                ),
+                counts,
+            );
+        });
+
+        HiLoEquityResult::new(counts[0], counts[1], counts[2], counts[3])
+    }
+
+    /// Eight-or-better hi-lo result of `hero` against `villain` with `board` dealt and `dead`
+    /// cards out of the deck, enumerated across [`Equitizer::num_threads`] threads. Each half
+    /// is the best five of the seven cards on its own, as in hold'em hi-lo or the seven cards
+    /// of a Stud-8 showdown. Not cached.
+    pub fn hi_lo_hand_vs_hand(
+        &self,
+        hero: Combo,
+        villain: Combo,
+        board: &[Card],
+        dead: &[Card],
+    ) -> HiLoEquityResult {
+        check_known_cards(
+            self.hand_ranker,
+            &[hero.0, hero.1, villain.0, villain.1],
+            board,
+            dead,
+        );
+        let deck = self
+            .hand_ranker
+            .deck()
+            .into_iter()
+            .filter(|&c| {
+                !hero.contains(c)
+                    && !villain.contains(c)
+                    && !board.contains(&c)
+                    && !dead.contains(&c)
+            })
+            .collect::<Vec<_>>();
+        let low_ranker = self.low_ranker();
+
+        let counts = count_runouts(&deck, board, self.num_threads, |full_board, counts| {
+            let [b0, b1, b2, b3, b4] = *full_board;
+            let hero_cards = [hero.0, hero.1, b0, b1, b2, b3, b4];
+            let villain_cards = [villain.0, villain.1, b0, b1, b2, b3, b4];
+            count_hi_lo_showdown(
+                (
+                    self.hand_ranker.get7(hero_cards),
+                    low_ranker.eight_or_better7(hero_cards),
+                ),
+                (
+                    self.hand_ranker.get7(villain_cards),
+                    low_ranker.eight_or_better7(villain_cards),
+                ),
+                counts,
+            );
+        });
+
+        HiLoEquityResult::new(counts[0], counts[1], counts[2], counts[3])
+    }
+
+    fn low_ranker(&self) -> &LowRanker {
+        self.low_ranker.get_or_init(LowRanker::generate)
+    }
+
+    pub fn query_eq(&self, lhs: &impl Range, rhs: &impl Range) -> f64 {
+        self.range_vs_range(lhs, rhs).equity()
+    }
+
+    pub fn query_eq_on_board(&self, lhs: &impl Range, rhs: &impl Range, board: &[Card]) -> f64 {
+        self.range_vs_range_on_board(lhs, rhs, board).equity()
+    }
+
+    /// Equity of every player when `ranges` (2 to 9 of them) go to showdown together.
+    ///
+    /// Every card-compatible combination of combos is weighted by the product of the combo
+    /// weights, and a pot tied between k players gives each of them 1/k. Runouts are enumerated
+    /// exactly, so the query is refused when that would take more than
+    /// [`Equitizer::MAX_MULTIWAY_EVALUATIONS`] hand evaluations.
+    pub fn multiway_equity<R: Range>(
+        &self,
+        ranges: &[&R],
+        board: &[Card],
+        dead: &[Card],
+    ) -> Result<Vec<f64>, StackedError> {
+        let ranges = ranges
+            .iter()
+            .map(|range| range.iter_weighted_combos().collect())
+            .collect::<Vec<_>>();
+        self.multiway_equity_weighted(&ranges, board, dead)
+    }
+
+    /// [`Equitizer::multiway_equity`] over ranges given as their weighted combos, so that
+    /// players can hold different kinds of range.
+    pub fn multiway_equity_weighted(
+        &self,
+        ranges: &[Vec<WeightedCombo>],
+        board: &[Card],
+        dead: &[Card],
+    ) -> Result<Vec<f64>, StackedError> {
+        if !(2..=9).contains(&ranges.len()) {
+            return format_stacked_err!("invalid number of players: {}", ranges.len());
+        }
+
+        check_board_and_dead(self.hand_ranker, board, dead)?;
+
+        if ranges.len() == 2 {
+            let res = self.range_vs_range_with_dead(&ranges[0], &ranges[1], board, dead);
+            if res.total == 0.0 {
+                return format_stacked_err!("no compatible combos found");
+            }
+            return Ok(vec![res.equity(), 1.0 - res.equity()]);
+        }
+
+        let weighted_combos = ranges
+            .iter()
+            .map(|range| live_combos(self.hand_ranker, range, board, dead))
+            .collect::<Vec<_>>();
+
+        let num_unknown_cards = self
+            .hand_ranker
+            .deck()
+            .len()
+            .saturating_sub(board.len() + dead.len() + 2 * ranges.len());
+        let num_evaluations = weighted_combos
+            .iter()
+            .fold(ranges.len() as f64, |acc, wcs| acc * wcs.len() as f64)
+            * num_runouts(num_unknown_cards, 5 - board.len());
+        if num_evaluations > Self::MAX_MULTIWAY_EVALUATIONS {
+            return format_stacked_err!(
+                "too many runouts to enumerate: ~{:e} hand evaluations, try multiway_equity_monte_carlo",
+                num_evaluations
+            );
+        }
+
+        let mut sum_eqs = vec![0.0; ranges.len()];
+        let mut sum_weights = 0.0;
+        let mut combos = Vec::with_capacity(ranges.len());
+        // suit-isomorphic deals have the same equities
+        let mut eqs_by_deal = HashMap::new();
+
+        for_each_deal(&weighted_combos, &mut combos, 1.0, &mut |combos, weight| {
+            let hands = combos.iter().map(|c| [c.0, c.1]).collect::<Vec<_>>();
+            let hands = hands.iter().map(|h| h.as_slice()).collect::<Vec<_>>();
+            let eqs = eqs_by_deal
+                .entry(canonicalize(&hands, board, dead).into_key())
+                .or_insert_with(|| self.calc_multiway(combos, board, dead));
+            for (sum_eq, eq) in sum_eqs.iter_mut().zip(eqs.iter()) {
+                *sum_eq += eq * weight;
+            }
+            sum_weights += weight;
+        });
+
+        if sum_weights == 0.0 {
+            return format_stacked_err!("no compatible combos found");
+        }
+
+        Ok(sum_eqs.iter().map(|eq| eq / sum_weights).collect())
+    }
+
+    /// Enumerates every runout of `board` and returns each player's share of the pot.
+    pub fn calc_multiway(&self, combos: &[Combo], board: &[Card], dead: &[Card]) -> Vec<f64> {
+        let mut shares = vec![0.0; combos.len()];
+        let mut num_runouts = 0;
+        let mut hand_ranks = vec![HandRank::ERROR; combos.len()];
+
+        let deck = self
+            .hand_ranker
+            .deck()
+            .into_iter()
+            .filter(|&c| {
+                !combos.iter().any(|combo| combo.contains(c))
+                    && !board.contains(&c)
+                    && !dead.contains(&c)
+            })
+            .collect::<Vec<_>>();
+
+        let mut full_board = [Card::from_value(0); 5];
+        full_board[..board.len()].copy_from_slice(board);
+
+        for_each_runout(&deck, 0, &mut full_board, board.len(), &mut |full_board| {
+            self.showdown(combos, full_board, &mut hand_ranks);
+
+            let best = *hand_ranks.iter().max().unwrap();
+            let num_winners = hand_ranks.iter().filter(|&&r| r == best).count();
+            for (share, &hand_rank) in shares.iter_mut().zip(&hand_ranks) {
+                if hand_rank == best {
+                    *share += 1.0 / num_winners as f64;
                 }
             }
+            num_runouts += 1;
+        });
+
+        shares
+            .iter()
+            .map(|share| share / num_runouts as f64)
+            .collect()
+    }
+
+    fn showdown(&self, combos: &[Combo], full_board: &[Card; 5], hand_ranks: &mut [HandRank]) {
+        let board = PartialHand::from_cards(full_board);
+        for (hand_rank, combo) in hand_ranks.iter_mut().zip(combos) {
+            *hand_rank = self
+                .hand_ranker
+                .evaluate_partial(&board.with_card(combo.0).with_card(combo.1));
         }
+    }
 
-        let key = (hero.0, hero.1, villain.0, villain.1);
-        match self.cache.get(&key) {
-            Some(equity) => return *equity,
-            None => {}
-        };
+    /// Estimates the equity of `lhs` against `rhs` by sampling deals and runouts with `rng`.
+    ///
+    /// Combos are drawn in proportion to their weights and redrawn when they share a card,
+    /// so the estimate converges to [`Equitizer::range_vs_range_with_dead`].
+    pub fn range_vs_range_monte_carlo(
+        &self,
+        lhs: &impl Range,
+        rhs: &impl Range,
+        board: &[Card],
+        dead: &[Card],
+        config: &MonteCarloConfig,
+        rng: &mut impl Rng,
+    ) -> Result<MonteCarloResult, StackedError> {
+        check_board_and_dead(self.hand_ranker, board, dead)?;
 
-        let equity = self.calc_combo_vs_combo(hero, villain);
-        self.cache.insert(key, equity);
-
-        std::fs::OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(Self::CACHE_FILENAME)
-            .unwrap()
-            .write_all(
-                format!(
-                    "{:?}{:?} {:?}{:?} {:?}\n",
-                    hero.0, hero.1, villain.0, villain.1, equity
-                )
-                .as_bytes(),
-            )
-            .unwrap();
+        let weighted_combos = [
+            live_combos(self.hand_ranker, lhs, board, dead),
+            live_combos(self.hand_ranker, rhs, board, dead),
+        ];
+        let results = self.sample_equities(&weighted_combos, board, dead, config, rng)?;
 
-        equity
+        Ok(results[0])
     }
 
-    pub fn calc_combo_vs_combo(&mut self, lhs: Combo, rhs: Combo) -> f64 {
-        let mut win = 0;
-        let mut lose = 0;
-        let mut tie = 0;
+    /// Sampling counterpart of [`Equitizer::multiway_equity`] for spots too large to enumerate.
+    pub fn multiway_equity_monte_carlo<R: Range>(
+        &self,
+        ranges: &[&R],
+        board: &[Card],
+        dead: &[Card],
+        config: &MonteCarloConfig,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<MonteCarloResult>, StackedError> {
+        let ranges = ranges
+            .iter()
+            .map(|range| range.iter_weighted_combos().collect())
+            .collect::<Vec<_>>();
+        self.multiway_equity_monte_carlo_weighted(&ranges, board, dead, config, rng)
+    }
+
+    /// Sampling counterpart of [`Equitizer::multiway_equity_weighted`].
+    pub fn multiway_equity_monte_carlo_weighted(
+        &self,
+        ranges: &[Vec<WeightedCombo>],
+        board: &[Card],
+        dead: &[Card],
+        config: &MonteCarloConfig,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<MonteCarloResult>, StackedError> {
+        if !(2..=9).contains(&ranges.len()) {
+            return format_stacked_err!("invalid number of players: {}", ranges.len());
+        }
+
+        check_board_and_dead(self.hand_ranker, board, dead)?;
+
+        let weighted_combos = ranges
+            .iter()
+            .map(|range| live_combos(self.hand_ranker, range, board, dead))
+            .collect::<Vec<_>>();
+
+        self.sample_equities(&weighted_combos, board, dead, config, rng)
+    }
+
+    fn sample_equities(
+        &self,
+        weighted_combos: &[Vec<WeightedCombo>],
+        board: &[Card],
+        dead: &[Card],
+        config: &MonteCarloConfig,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<MonteCarloResult>, StackedError> {
+        if !matches!(board.len(), 0 | 3..=5) {
+            return format_stacked_err!("invalid board length: {:?}", board);
+        }
+
+        let mut cum_weights = Vec::with_capacity(weighted_combos.len());
+        for wcs in weighted_combos {
+            let mut sum_weights = 0.0;
+            let cum = wcs
+                .iter()
+                .map(|wc| {
+                    sum_weights += wc.weight;
+                    sum_weights
+                })
+                .collect::<Vec<_>>();
+            if sum_weights <= 0.0 {
+                return format_stacked_err!("no live combos in range");
+            }
+            cum_weights.push(cum);
+        }
 
-        let deck = (0..52)
-            .map(|c| Card::from_value(c))
-            .filter(|&c| c != lhs.0 && c != lhs.1 && c != rhs.0 && c != rhs.1)
+        let deck = self
+            .hand_ranker
+            .deck()
+            .into_iter()
+            .filter(|c| !board.contains(c) && !dead.contains(c))
             .collect::<Vec<_>>();
 
-        for i4 in 0..deck.len() {
-            for i3 in 0..i4 {
-                for i2 in 0..i3 {
-                    for i1 in 0..i2 {
-                        for i0 in 0..i1 {
-                            let board = [deck[i0], deck[i1], deck[i2], deck[i3], deck[i4]];
-                            let hero = [
-                                lhs.0, lhs.1, board[0], board[1], board[2], board[3], board[4],
-                            ];
-                            let villain = [
-                                rhs.0, rhs.1, board[0], board[1], board[2], board[3], board[4],
-                            ];
-
-                            let hero_hand_rank = self.hand_ranker.get7(hero);
-                            let villain_hand_rank = self.hand_ranker.get7(villain);
-
-                            match hero_hand_rank.cmp(&villain_hand_rank) {
-                                std::cmp::Ordering::Greater => win += 1,
-                                std::cmp::Ordering::Equal => tie += 1,
-                                std::cmp::Ordering::Less => lose += 1,
-                            }
+        let num_players = weighted_combos.len();
+        let mut combos = vec![weighted_combos[0][0].combo; num_players];
+        let mut hand_ranks = vec![HandRank::ERROR; num_players];
+        let mut sums = vec![0.0; num_players];
+        let mut sum_sqs = vec![0.0; num_players];
+        let mut full_board = [Card::from_value(0); 5];
+        full_board[..board.len()].copy_from_slice(board);
+
+        let start = Instant::now();
+        let mut num_samples = 0;
+
+        loop {
+            for _ in 0..config.batch_size.max(1) {
+                let mut num_rejections = 0;
+                let mut player = 0;
+                while player < num_players {
+                    let cum = &cum_weights[player];
+                    let x = rng.gen::<f64>() * cum[cum.len() - 1];
+                    let i = cum.partition_point(|&c| c <= x).min(cum.len() - 1);
+                    combos[player] = weighted_combos[player][i].combo;
+
+                    if combos[..player]
+                        .iter()
+                        .any(|c| c.intersects(&combos[player]))
+                    {
+                        num_rejections += 1;
+                        if num_rejections > Self::MAX_DEAL_REJECTIONS {
+                            return format_stacked_err!("no compatible combos found");
                         }
+                        player = 0;
+                        continue;
+                    }
+                    player += 1;
+                }
+
+                let mut num_board_cards = board.len();
+                while num_board_cards < 5 {
+                    let card = deck[rng.gen_range(0..deck.len())];
+                    if combos.iter().any(|c| c.contains(card))
+                        || full_board[board.len()..num_board_cards].contains(&card)
+                    {
+                        continue;
                     }
+                    full_board[num_board_cards] = card;
+                    num_board_cards += 1;
                 }
+
+                self.showdown(&combos, &full_board, &mut hand_ranks);
+
+                let best = *hand_ranks.iter().max().unwrap();
+                let num_winners = hand_ranks.iter().filter(|&&r| r == best).count();
+                for (player, &hand_rank) in hand_ranks.iter().enumerate() {
+                    if hand_rank == best {
+                        let share = 1.0 / num_winners as f64;
+                        sums[player] += share;
+                        sum_sqs[player] += share * share;
+                    }
+                }
+                num_samples += 1;
             }
-        }
 
-        let equity = ((tie as f64) * 0.5 + (win as f64)) / ((win + lose + tie) as f64);
+            let results = sums
+                .iter()
+                .zip(&sum_sqs)
+                .map(|(&sum, &sum_sq)| MonteCarloResult::from_sums(sum, sum_sq, num_samples))
+                .collect::<Vec<_>>();
 
-        equity
-    }
+            let precise_enough = config
+                .target_std_err
+                .is_some_and(|target_std_err| results.iter().all(|r| r.std_err <= target_std_err));
+            let out_of_time = config
+                .time_budget
+                .is_some_and(|time_budget| start.elapsed() >= time_budget);
 
-    pub fn query_eq(&mut self, lhs: &impl Range, rhs: &impl Range) -> f64 {
-        self.range_vs_range(lhs, rhs)
+            if num_samples >= config.max_samples || precise_enough || out_of_time {
+                return Ok(results);
+            }
+        }
     }
 
     pub fn query_sub_prob(
-        &mut self,
+        &self,
         blockers: &PureRange,
         sub_rhs: &impl Range,
         full_rhs: &impl Range,
@@ -288,11 +1008,11 @@ impl<'a> Equitizer<'a> {
             res.push(total_sub_weights / total_weights);
         }
 
-        if res.len() == 0 {
+        if res.is_empty() {
             panic!("No combos found");
         }
 
-        let first_res = res[0].clone();
+        let first_res = res[0];
 
         for x in &res {
             if *x != first_res {
@@ -303,28 +1023,52 @@ impl<'a> Equitizer<'a> {
         first_res
     }
 
-    pub fn query_prob(&mut self, blockers: &PureRange, range: &impl Range) -> f64 {
-        const C_50_2: f64 = 50.0 * 49.0 / 2.0;
+    pub fn query_prob(&self, blockers: &PureRange, range: &impl Range) -> f64 {
+        self.query_prob_with_dead(blockers, range, &[]).unwrap()
+    }
+
+    /// Like [`Equitizer::query_prob`], but `dead` cards cannot be held by the range, and the
+    /// number of unknown hands shrinks accordingly. Errs on dead cards that are repeated,
+    /// outside the deck or in a blocker combo.
+    pub fn query_prob_with_dead(
+        &self,
+        blockers: &PureRange,
+        range: &impl Range,
+        dead: &[Card],
+    ) -> Result<f64, StackedError> {
+        check_board_and_dead(self.hand_ranker, &[], dead)?;
+        if let Some(blocker_combo) = blockers
+            .combos
+            .iter()
+            .find(|combo| combo.intersects_cards(dead))
+        {
+            return format_stacked_err!("dead card in blocker combo: {:?}", blocker_combo);
+        }
+
+        let num_unknown_cards = (self.hand_ranker.deck().len() - 2 - dead.len()) as f64;
+        let num_unknown_combos = num_unknown_cards * (num_unknown_cards - 1.0) / 2.0;
 
         let mut res = Vec::new();
 
         for &blocker_combo in &blockers.combos {
             let mut sum_weights = 0.0;
             for weighted_combo in range.iter_weighted_combos() {
-                if blocker_combo.intersects(&weighted_combo.combo) {
+                if blocker_combo.intersects(&weighted_combo.combo)
+                    || weighted_combo.combo.intersects_cards(dead)
+                {
                     continue;
                 }
 
                 sum_weights += weighted_combo.weight;
             }
-            res.push(sum_weights / C_50_2);
+            res.push(sum_weights / num_unknown_combos);
         }
 
-        if res.len() == 0 {
+        if res.is_empty() {
             panic!("No combos found");
         }
 
-        let first_res = res[0].clone();
+        let first_res = res[0];
 
         for x in &res {
             if *x != first_res {
@@ -332,10 +1076,10 @@ impl<'a> Equitizer<'a> {
             }
         }
 
-        first_res
+        Ok(first_res)
     }
 
-    pub fn query_prob_and_eq(&mut self, lhs: &PureRange, rhs: &impl Range) -> (f64, f64) {
+    pub fn query_prob_and_eq(&self, lhs: &PureRange, rhs: &impl Range) -> (f64, f64) {
         let prob = self.query_prob(lhs, rhs);
         let eq = self.query_eq(lhs, rhs);
 
@@ -343,7 +1087,7 @@ impl<'a> Equitizer<'a> {
     }
 
     pub fn query_sub_prob_and_eq(
-        &mut self,
+        &self,
         lhs: &PureRange,
         rhs: &impl Range,
         full_rhs: &impl Range,
@@ -355,18 +1099,935 @@ impl<'a> Equitizer<'a> {
     }
 }
 
+/// Maps a preflop matchup onto the suit pattern under which it is cached: hero's suits become
+/// clubs, then diamonds, and villain's unseen suits take the next free ones.
+pub(crate) fn normalize_preflop(mut hero: Combo, mut villain: Combo) -> (Combo, Combo) {
+    if hero.0.suit() == hero.1.suit() {
+        let hero_suit = hero.0.suit();
+        if villain.0.suit() == villain.1.suit() {
+            if hero.0.suit() == villain.0.suit() {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::CLUB);
+                villain.0 = villain.0.with_suit(&Suit::CLUB);
+                villain.1 = villain.1.with_suit(&Suit::CLUB);
+            } else {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::CLUB);
+                villain.0 = villain.0.with_suit(&Suit::DIAMOND);
+                villain.1 = villain.1.with_suit(&Suit::DIAMOND);
+            }
+        } else {
+            if hero_suit == villain.0.suit() {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::CLUB);
+                villain.0 = villain.0.with_suit(&Suit::CLUB);
+                villain.1 = villain.1.with_suit(&Suit::DIAMOND);
+            } else if hero_suit == villain.1.suit() {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::CLUB);
+                villain.0 = villain.0.with_suit(&Suit::DIAMOND);
+                villain.1 = villain.1.with_suit(&Suit::CLUB);
+            } else {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::CLUB);
+                villain.0 = villain.0.with_suit(&Suit::DIAMOND);
+                villain.1 = villain.1.with_suit(&Suit::HEART);
+            }
+        }
+    } else {
+        if villain.0.suit() == villain.1.suit() {
+            let villain_suit = villain.0.suit();
+            if hero.0.suit() == villain_suit {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::DIAMOND);
+                villain.0 = villain.0.with_suit(&Suit::CLUB);
+                villain.1 = villain.1.with_suit(&Suit::CLUB);
+            } else if hero.1.suit() == villain_suit {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::DIAMOND);
+                villain.0 = villain.0.with_suit(&Suit::DIAMOND);
+                villain.1 = villain.1.with_suit(&Suit::DIAMOND);
+            } else {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::DIAMOND);
+                villain.0 = villain.0.with_suit(&Suit::HEART);
+                villain.1 = villain.1.with_suit(&Suit::HEART);
+            }
+        } else {
+            if hero.0.suit() == villain.0.suit() {
+                if hero.1.suit() == villain.1.suit() {
+                    hero.0 = hero.0.with_suit(&Suit::CLUB);
+                    hero.1 = hero.1.with_suit(&Suit::DIAMOND);
+                    villain.0 = villain.0.with_suit(&Suit::CLUB);
+                    villain.1 = villain.1.with_suit(&Suit::DIAMOND);
+                } else {
+                    hero.0 = hero.0.with_suit(&Suit::CLUB);
+                    hero.1 = hero.1.with_suit(&Suit::DIAMOND);
+                    villain.0 = villain.0.with_suit(&Suit::CLUB);
+                    villain.1 = villain.1.with_suit(&Suit::HEART);
+                }
+            } else if hero.0.suit() == villain.1.suit() {
+                if hero.1.suit() == villain.0.suit() {
+                    hero.0 = hero.0.with_suit(&Suit::CLUB);
+                    hero.1 = hero.1.with_suit(&Suit::DIAMOND);
+                    villain.0 = villain.0.with_suit(&Suit::DIAMOND);
+                    villain.1 = villain.1.with_suit(&Suit::CLUB);
+                } else {
+                    hero.0 = hero.0.with_suit(&Suit::CLUB);
+                    hero.1 = hero.1.with_suit(&Suit::DIAMOND);
+                    villain.0 = villain.0.with_suit(&Suit::HEART);
+                    villain.1 = villain.1.with_suit(&Suit::CLUB);
+                }
+            } else if hero.1.suit() == villain.0.suit() {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::DIAMOND);
+                villain.0 = villain.0.with_suit(&Suit::DIAMOND);
+                villain.1 = villain.1.with_suit(&Suit::HEART);
+            } else if hero.1.suit() == villain.1.suit() {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::DIAMOND);
+                villain.0 = villain.0.with_suit(&Suit::HEART);
+                villain.1 = villain.1.with_suit(&Suit::DIAMOND);
+            } else {
+                hero.0 = hero.0.with_suit(&Suit::CLUB);
+                hero.1 = hero.1.with_suit(&Suit::DIAMOND);
+                villain.0 = villain.0.with_suit(&Suit::HEART);
+                villain.1 = villain.1.with_suit(&Suit::SPADE);
+            }
+        }
+    }
+
+    (hero, villain)
+}
+
+fn check_known_cards(hand_ranker: &HandRanker, hole_cards: &[Card], board: &[Card], dead: &[Card]) {
+    if !matches!(board.len(), 0 | 3..=5) {
+        panic!("Invalid board length: {:?}", board);
+    }
+
+    let mut known_cards = Vec::new();
+    for &card in hole_cards.iter().chain(board).chain(dead) {
+        if !hand_ranker.in_deck(card) {
+            panic!("Card not in the deck: {:?}", card);
+        }
+        if known_cards.contains(&card) {
+            panic!("Duplicated card: {:?}", card);
+        }
+        known_cards.push(card);
+    }
+}
+
+/// Errs on a board of the wrong length, and on board or dead cards that are repeated or
+/// outside the deck.
+fn check_board_and_dead(
+    hand_ranker: &HandRanker,
+    board: &[Card],
+    dead: &[Card],
+) -> Result<(), StackedError> {
+    if !matches!(board.len(), 0 | 3..=5) {
+        return format_stacked_err!("invalid board length: {:?}", board);
+    }
+
+    let mut known_cards = Vec::new();
+    for &card in board.iter().chain(dead) {
+        if !hand_ranker.in_deck(card) {
+            return format_stacked_err!("card not in the deck: {:?}", card);
+        }
+        if known_cards.contains(&card) {
+            return format_stacked_err!("duplicated card: {:?}", card);
+        }
+        known_cards.push(card);
+    }
+
+    Ok(())
+}
+
+/// Combos of `range` that are in the deck and use neither a board card nor a dead card.
+fn live_combos(
+    hand_ranker: &HandRanker,
+    range: &impl Range,
+    board: &[Card],
+    dead: &[Card],
+) -> Vec<WeightedCombo> {
+    range
+        .iter_weighted_combos()
+        .filter(|wc| {
+            hand_ranker.in_deck(wc.combo.0)
+                && hand_ranker.in_deck(wc.combo.1)
+                && !wc.combo.intersects_cards(board)
+                && !wc.combo.intersects_cards(dead)
+        })
+        .collect()
+}
+
+fn num_runouts(num_cards: usize, num_board_cards: usize) -> f64 {
+    (0..num_board_cards).fold(1.0, |acc, i| {
+        acc * num_cards.saturating_sub(i) as f64 / (i + 1) as f64
+    })
+}
+
+/// Outcome of [`Equitizer::lookup`]: the result, or the cache key to store it under.
+enum Lookup {
+    Hit(EquityResult),
+    Miss(EquityKey),
+}
+
+/// Calls `f` with every card-compatible choice of one combo per player and its weight.
+fn for_each_deal(
+    weighted_combos: &[Vec<WeightedCombo>],
+    combos: &mut Vec<Combo>,
+    weight: f64,
+    f: &mut impl FnMut(&[Combo], f64),
+) {
+    let player = combos.len();
+    if player == weighted_combos.len() {
+        f(combos, weight);
+        return;
+    }
+
+    for weighted_combo in &weighted_combos[player] {
+        if combos.iter().any(|c| c.intersects(&weighted_combo.combo)) {
+            continue;
+        }
+
+        combos.push(weighted_combo.combo);
+        for_each_deal(weighted_combos, combos, weight * weighted_combo.weight, f);
+        combos.pop();
+    }
+}
+
+/// Adds `count` up over every runout that completes `board` with cards of `deck`. The first
+/// dealt card is dealt round-robin across `num_threads` threads, as low cards leave more runouts
+/// behind.
+fn count_runouts<const N: usize>(
+    deck: &[Card],
+    board: &[Card],
+    num_threads: usize,
+    count: impl Fn(&[Card; 5], &mut [u64; N]) + Sync,
+) -> [u64; N] {
+    let mut full_board = [Card::from_value(0); 5];
+    full_board[..board.len()].copy_from_slice(board);
+
+    let count_from = |first_cards: &mut dyn Iterator<Item = usize>| {
+        let mut full_board = full_board;
+        let mut counts = [0; N];
+        let mut showdown = |full_board: &[Card; 5]| count(full_board, &mut counts);
+
+        for i in first_cards {
+            full_board[board.len()] = deck[i];
+            for_each_runout(deck, i + 1, &mut full_board, board.len() + 1, &mut showdown);
+        }
+        counts
+    };
+
+    if board.len() == 5 {
+        let mut counts = [0; N];
+        count(&full_board, &mut counts);
+        return counts;
+    }
+
+    if num_threads == 1 {
+        return count_from(&mut (0..deck.len()));
+    }
+
+    std::thread::scope(|scope| {
+        let handles = (0..num_threads)
+            .map(|thread| {
+                let count_from = &count_from;
+                scope.spawn(move || count_from(&mut (thread..deck.len()).step_by(num_threads)))
+            })
+            .collect::<Vec<_>>();
+
+        handles.into_iter().fold([0; N], |mut acc, handle| {
+            let counts = handle.join().unwrap();
+            for (sum, count) in acc.iter_mut().zip(counts) {
+                *sum += count;
+            }
+            acc
+        })
+    })
+}
+
+/// Counts a showdown into (wins, ties, losses) from the hero's point of view.
+fn count_showdown(hero_hand_rank: HandRank, villain_hand_rank: HandRank, counts: &mut [u64; 3]) {
+    match hero_hand_rank.cmp(&villain_hand_rank) {
+        std::cmp::Ordering::Greater => counts[0] += 1,
+        std::cmp::Ordering::Equal => counts[1] += 1,
+        std::cmp::Ordering::Less => counts[2] += 1,
+    }
+}
+
+/// Counts a hi-lo showdown of (high, low) hands into (high quarters, low quarters, scoops,
+/// runouts) of the pot from the hero's point of view. Without a qualifying low on either side
+/// the high hand takes all four quarters.
+fn count_hi_lo_showdown(
+    (hero_high, hero_low): (HandRank, LowRank),
+    (villain_high, villain_low): (HandRank, LowRank),
+    counts: &mut [u64; 4],
+) {
+    let has_low = hero_low.is_low() || villain_low.is_low();
+    let half = |hero: std::cmp::Ordering, quarters| match hero {
+        std::cmp::Ordering::Greater => quarters,
+        std::cmp::Ordering::Equal => quarters / 2,
+        std::cmp::Ordering::Less => 0,
+    };
+
+    let (high, low) = if has_low {
+        (
+            half(hero_high.cmp(&villain_high), 2),
+            half(hero_low.cmp(&villain_low), 2),
+        )
+    } else {
+        (half(hero_high.cmp(&villain_high), 4), 0)
+    };
+
+    counts[0] += high;
+    counts[1] += low;
+    if high + low == 4 {
+        counts[2] += 1;
+    }
+    counts[3] += 1;
+}
+
+/// Fills `full_board[pos..]` with every combination of the remaining `deck[start..]` cards.
+fn for_each_runout(
+    deck: &[Card],
+    start: usize,
+    full_board: &mut [Card; 5],
+    pos: usize,
+    f: &mut impl FnMut(&[Card; 5]),
+) {
+    if pos == full_board.len() {
+        f(full_board);
+        return;
+    }
+
+    for i in start..deck.len() {
+        full_board[pos] = deck[i];
+        for_each_runout(deck, i + 1, full_board, pos + 1, f);
+    }
+}
+
+/// The [`canonicalize`]d cards of the matchup, so that suit-isomorphic
+/// (hero, villain, board, dead) tuples share one cache entry.
+fn canonical_key(hero: Combo, villain: Combo, board: &[Card], dead: &[Card]) -> EquityKey {
+    let canonical = canonicalize(&[&[hero.0, hero.1], &[villain.0, villain.1]], board, dead);
+    let combo = |cards: &[Card]| Combo(cards[0], cards[1]);
+
+    EquityKey {
+        hero: combo(&canonical.hands[0]),
+        villain: combo(&canonical.hands[1]),
+        board: canonical.board,
+        dead: canonical.dead,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::equity_cache::MemoryCache;
+    use crate::types::MixedRange;
+    use std::sync::OnceLock;
+
+    fn hand_ranker() -> &'static HandRanker {
+        static HAND_RANKER: OnceLock<HandRanker> = OnceLock::new();
+        HAND_RANKER.get_or_init(HandRanker::generate)
+    }
+
+    fn combo(abbr: &str) -> Combo {
+        Combo::new(
+            Card::parse(&abbr[0..2]).unwrap(),
+            Card::parse(&abbr[2..4]).unwrap(),
+        )
+    }
+
+    fn combo_of_values(c1: i32, c2: i32) -> Combo {
+        Combo::new(Card::from_value(c1), Card::from_value(c2))
+    }
+
+    fn cards(abbr: &str) -> Vec<Card> {
+        (0..abbr.len())
+            .step_by(2)
+            .map(|i| Card::parse(&abbr[i..i + 2]).unwrap())
+            .collect()
+    }
 
     #[test]
     fn test_query_sub_prob() {
-        let data_dir = "./data";
-        let hand_ranker = HandRanker::new(&data_dir).unwrap();
-        let mut equitizer = Equitizer::new(&hand_ranker).unwrap();
+        let equitizer = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
         let aa = PureRange::from("AA");
         let aa_kk = PureRange::from("AA,KK");
         let eq = equitizer.query_sub_prob(&aa, &aa, &aa_kk);
         assert_eq!(eq, 1.0 / 7.0);
     }
+
+    #[test]
+    fn test_canonical_key() {
+        let key = canonical_key(combo("AsKs"), combo("QdQc"), &cards("Td9s2c"), &[]);
+        let isomorphic_key = canonical_key(combo("AhKh"), combo("QsQd"), &cards("2dTs9h"), &[]);
+        assert_eq!(key, isomorphic_key);
+        // the key existing cache files hold, with the board in ascending order
+        assert_eq!(
+            key,
+            EquityKey {
+                hero: combo("AcKc"),
+                villain: combo("QhQd"),
+                board: cards("2d9cTh"),
+                dead: Vec::new(),
+            }
+        );
+
+        let key = canonical_key(combo("AsKs"), combo("QdQc"), &[], &cards("Qs"));
+        let other_key = canonical_key(combo("AsKs"), combo("QdQc"), &[], &cards("Qh"));
+        assert_ne!(key, other_key);
+    }
+
+    #[test]
+    fn test_hand_vs_hand_on_board() {
+        let equitizer = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+
+        let river = cards("Td9s2c3h4d");
+        assert_eq!(
+            equitizer.hand_vs_hand_on_board(combo("AsKs"), combo("QdQc"), &river),
+            EquityResult::new(0, 0, 1)
+        );
+        assert_eq!(
+            equitizer.hand_vs_hand_on_board(combo("AsKs"), combo("AhKh"), &river),
+            EquityResult::new(0, 1, 0)
+        );
+
+        // 44 rivers, hero needs one of the 6 remaining aces or kings.
+        let turn = cards("Td9s2c3h");
+        assert_eq!(
+            equitizer.hand_vs_hand_on_board(combo("AsKs"), combo("QdQc"), &turn),
+            EquityResult::new(6, 0, 38)
+        );
+    }
+
+    #[test]
+    fn test_dead_cards() {
+        let equitizer = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+
+        // Two of the six outs are dead, 42 rivers are left.
+        let turn = cards("Td9s2c3h");
+        let dead = cards("AhKh");
+        assert_eq!(
+            equitizer.hand_vs_hand_with_dead(combo("AsKs"), combo("QdQc"), &turn, &dead),
+            EquityResult::new(4, 0, 38)
+        );
+
+        // With four aces dead, only the kings are left and every pair of them is unblocked.
+        let aa_kk = PureRange::from("AA,KK");
+        let kk = PureRange::from("KK");
+        let dead = cards("AsAhAdAc");
+        let prob = equitizer
+            .query_prob_with_dead(&PureRange::from("QQ"), &aa_kk, &dead)
+            .unwrap();
+        assert_eq!(prob, 6.0 / (46.0 * 45.0 / 2.0));
+
+        // a repeated dead card or one the blockers hold is refused
+        let qq = PureRange::from("QQ");
+        assert!(equitizer
+            .query_prob_with_dead(&qq, &aa_kk, &cards("AsAs"))
+            .is_err());
+        assert!(equitizer
+            .query_prob_with_dead(&qq, &aa_kk, &cards("Qs"))
+            .is_err());
+        let res = equitizer.range_vs_range_with_dead(&kk, &aa_kk, &turn, &dead);
+        assert_eq!(res.tie_freq(), 1.0);
+        assert_eq!(res.equity(), 0.5);
+    }
+
+    #[test]
+    fn test_multiway_equity() {
+        let equitizer = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+
+        // Three-way chop on a board that plays.
+        let river = cards("AsKsQsJsTs");
+        let ranges = [
+            &PureRange::from("22"),
+            &PureRange::from("33"),
+            &PureRange::from("44"),
+        ];
+        let eqs = equitizer.multiway_equity(&ranges, &river, &[]).unwrap();
+        for eq in eqs {
+            assert!((eq - 1.0 / 3.0).abs() < 1e-12);
+        }
+
+        // Sets beat overpairs, two players split what the set leaves.
+        let turn = cards("Td9s2c3h");
+        let combos = [combo("TsTc"), combo("AsAh"), combo("AdAc")];
+        let eqs = equitizer.calc_multiway(&combos, &turn, &[]);
+        assert!((eqs.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(eqs[1], eqs[2]);
+        assert!(eqs[0] > 0.9);
+
+        assert!(equitizer
+            .multiway_equity(&[&PureRange::from("AA")], &[], &[])
+            .is_err());
+
+        // A mixed range next to pure ones, the same combos as the all-pure query.
+        let flop = cards("Td9s2c");
+        let pure_ranges = [
+            &PureRange::from("AKs"),
+            &PureRange::from("QQ"),
+            &PureRange::from("JJ"),
+        ];
+        let mixed_ranges = [
+            pure_ranges[0].iter_weighted_combos().collect(),
+            MixedRange::from("QQ").weighted_combos,
+            pure_ranges[2].iter_weighted_combos().collect(),
+        ];
+        let eqs = equitizer
+            .multiway_equity_weighted(&mixed_ranges, &flop, &[])
+            .unwrap();
+        assert_eq!(
+            eqs,
+            equitizer.multiway_equity(&pure_ranges, &flop, &[]).unwrap()
+        );
+        let eqs = equitizer
+            .multiway_equity_weighted(&mixed_ranges[..2], &flop, &[])
+            .unwrap();
+        assert_eq!(
+            eqs,
+            equitizer
+                .multiway_equity(&pure_ranges[..2], &flop, &[])
+                .unwrap()
+        );
+
+        // Ranges that block each other completely have no equity to share.
+        let blocked = [
+            vec![combo("AsAh").with_weight(1.0)],
+            vec![combo("AsAd").with_weight(1.0)],
+            PureRange::from("KK").iter_weighted_combos().collect(),
+        ];
+        for num_players in [2, 3] {
+            assert!(equitizer
+                .multiway_equity_weighted(&blocked[..num_players], &flop, &[])
+                .is_err());
+        }
+
+        // Repeated board and dead cards are refused whatever the number of players.
+        for num_players in [2, 3] {
+            let ranges = &pure_ranges[..num_players];
+            assert!(equitizer
+                .multiway_equity(ranges, &cards("Td9sTd"), &[])
+                .is_err());
+            assert!(equitizer
+                .multiway_equity(ranges, &flop, &cards("9s"))
+                .is_err());
+            assert!(equitizer
+                .multiway_equity(ranges, &flop, &cards("3h3h"))
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_monte_carlo() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let equitizer = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+        let config = MonteCarloConfig {
+            max_samples: 20_000,
+            target_std_err: Some(0.005),
+            ..Default::default()
+        };
+
+        let ak = PureRange::from("AKs");
+        let qq = PureRange::from("QQ");
+        let turn = cards("Td9s2c3h");
+        let res = equitizer
+            .range_vs_range_monte_carlo(
+                &ak,
+                &qq,
+                &turn,
+                &[],
+                &config,
+                &mut StdRng::seed_from_u64(7),
+            )
+            .unwrap();
+        assert!(res.num_samples <= config.max_samples);
+        assert!((res.equity - 6.0 / 44.0).abs() < 4.0 * res.std_err);
+
+        let same_res = equitizer
+            .range_vs_range_monte_carlo(
+                &ak,
+                &qq,
+                &turn,
+                &[],
+                &config,
+                &mut StdRng::seed_from_u64(7),
+            )
+            .unwrap();
+        assert_eq!(res, same_res);
+
+        // a repeated board card or a dead card on the board is refused, not sampled
+        for (board, dead) in [(cards("Td9sTd"), cards("")), (turn.clone(), cards("9s"))] {
+            assert!(equitizer
+                .range_vs_range_monte_carlo(
+                    &ak,
+                    &qq,
+                    &board,
+                    &dead,
+                    &config,
+                    &mut StdRng::seed_from_u64(7),
+                )
+                .is_err());
+        }
+
+        let ranges = [
+            ak.iter_weighted_combos().collect(),
+            MixedRange::from("QQ").weighted_combos,
+            PureRange::from("JJ").iter_weighted_combos().collect(),
+        ];
+        let results = equitizer
+            .multiway_equity_monte_carlo_weighted(
+                &ranges,
+                &turn,
+                &[],
+                &config,
+                &mut StdRng::seed_from_u64(7),
+            )
+            .unwrap();
+        let eqs = equitizer
+            .multiway_equity_weighted(&ranges, &turn, &[])
+            .unwrap();
+        for (res, eq) in results.iter().zip(eqs) {
+            assert!((res.equity - eq).abs() < 4.0 * res.std_err);
+        }
+        assert!(equitizer
+            .multiway_equity_monte_carlo_weighted(
+                &ranges,
+                &turn,
+                &turn[..1],
+                &config,
+                &mut StdRng::seed_from_u64(7),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_range_vs_range_by_combo() {
+        let equitizer = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+
+        let turn = cards("Td9s2c3h");
+        let hero = PureRange::from("AKs,QJs,TT");
+        let villain = PureRange::from("QQ");
+        let combo_equities = equitizer.range_vs_range_by_combo(&hero, &villain, &turn, &[]);
+
+        // TdTs, TdTh and TdTc are blocked by the board.
+        assert_eq!(combo_equities.len(), 4 + 4 + 3);
+
+        // every card-compatible pair of combos counts once, each matchup enumerated on its own
+        let mut res = EquityResult::default();
+        for combo_equity in &combo_equities {
+            res.add_weighted(&combo_equity.result, combo_equity.weight);
+        }
+        let mut expected = EquityResult::default();
+        for hero_combo in hero.iter_combos() {
+            for villain_combo in villain.iter_combos() {
+                if hero_combo.intersects_cards(&turn)
+                    || villain_combo.intersects_cards(&turn)
+                    || hero_combo.intersects(&villain_combo)
+                {
+                    continue;
+                }
+                let matchup_res = equitizer.hand_vs_hand_on_board(hero_combo, villain_combo, &turn);
+                expected.add_weighted(&matchup_res, 1.0);
+            }
+        }
+        assert_eq!(res.total, expected.total);
+        assert!((res.win_freq() - expected.win_freq()).abs() < 1e-12);
+        assert!((res.tie_freq() - expected.tie_freq()).abs() < 1e-12);
+
+        // QsJs blocks half of the queens.
+        let qjs = combo_equities
+            .iter()
+            .find(|ce| ce.combo == combo("QsJs"))
+            .unwrap();
+        assert_eq!(qjs.weight, 3.0);
+
+        // the batched enumeration agrees with enumerating each matchup on its own
+        let mut expected = EquityResult::default();
+        for queens in ["QdQc", "QhQd", "QhQc"] {
+            let matchup_res =
+                equitizer.calc_combo_vs_combo_on_board(combo("QsJs"), combo(queens), &turn);
+            expected.add_weighted(&matchup_res, 1.0);
+        }
+        assert_eq!(qjs.result, expected);
+    }
+
+    fn canonical_matchup(hero: Combo, villain: Combo) -> Vec<Vec<Card>> {
+        canonicalize(&[&[hero.0, hero.1], &[villain.0, villain.1]], &[], &[]).hands
+    }
+
+    #[test]
+    fn test_normalize_preflop() {
+        let combos = (0..52)
+            .flat_map(|c1| (0..c1).map(move |c2| combo_of_values(c1, c2)))
+            .collect::<Vec<_>>();
+
+        // every normalised matchup is a suit relabelling of the original one
+        for (i, &hero) in combos.iter().enumerate() {
+            for &villain in combos.iter().skip(i % 17).step_by(17) {
+                if hero.intersects(&villain) {
+                    continue;
+                }
+                let (normalized_hero, normalized_villain) = normalize_preflop(hero, villain);
+                assert_eq!(
+                    canonical_matchup(hero, villain),
+                    canonical_matchup(normalized_hero, normalized_villain),
+                    "{:?} {:?}",
+                    hero,
+                    villain
+                );
+            }
+        }
+
+        // Distinct normalised matchups are distinct spots, except that the table ignores the
+        // clubs/diamonds symmetry of a paired hero (2c2d vs 3d2h and 2c2d vs 3c2h) and keeps a
+        // paired villain's cards in either order (3c2d vs 3h3d and 3c2d vs 3d3h). Those only
+        // cost duplicate cache entries.
+        let mut seen = std::collections::HashSet::new();
+        for (hero, villain) in PreflopTable::canonical_matchups() {
+            if !seen.insert(canonical_matchup(hero, villain)) {
+                assert!(
+                    hero.0.rank() == hero.1.rank() || villain.0.rank() == villain.1.rank(),
+                    "{:?} {:?}",
+                    hero,
+                    villain
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_preflop_table() {
+        let matchups = PreflopTable::canonical_matchups();
+        assert!(matchups
+            .iter()
+            .all(|&(hero, villain)| normalize_preflop(hero, villain) == (hero, villain)));
+
+        let mut table = PreflopTable::new();
+        table.insert(combo("AsKs"), combo("QdQc"), EquityResult::new(1, 2, 3));
+
+        let mut equitizer = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+        equitizer.set_preflop_table(table);
+        assert_eq!(
+            equitizer.hand_vs_hand(combo("AhKh"), combo("QsQc")),
+            EquityResult::new(1, 2, 3)
+        );
+
+        // the shipped table covers every matchup with all 48 choose 5 runouts
+        let table = PreflopTable::embedded();
+        assert_eq!(table.len(), matchups.len());
+        for &(hero, villain) in &matchups {
+            let res = table.get(&(hero.0, hero.1, villain.0, villain.1)).unwrap();
+            assert_eq!(res.total, 1_712_304.0, "{:?} {:?}", hero, villain);
+        }
+        for &(hero, villain) in matchups.iter().step_by(matchups.len() / 3) {
+            assert_eq!(
+                table.get(&(hero.0, hero.1, villain.0, villain.1)),
+                Some(equitizer.calc_combo_vs_combo(hero, villain)),
+                "{:?} {:?}",
+                hero,
+                villain
+            );
+        }
+    }
+
+    #[test]
+    fn test_omaha_equity() {
+        let mut equitizer = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+        let hand = |abbr: &str| OmahaHand::parse(abbr).unwrap();
+        let (aakk, qqjj) = (hand("AsAhKdKc"), hand("QsQhJdJc"));
+
+        // hold'em would give villain a straight flush
+        let river = cards("2s3s4s5s9d");
+        let res = equitizer.omaha_hand_vs_hand(&aakk, &qqjj, &river, &[]);
+        assert_eq!(res, EquityResult::new(1, 0, 0));
+
+        let flop = cards("2c7d8h");
+        let res = equitizer.omaha_hand_vs_hand(&aakk, &qqjj, &flop, &[]);
+        assert_eq!(res.total, 820.0);
+        let swapped = equitizer.omaha_hand_vs_hand(&qqjj, &aakk, &flop, &[]);
+        assert_eq!((res.wins, res.ties), (swapped.losses, swapped.ties));
+
+        let plo5 = equitizer.omaha_hand_vs_hand(
+            &hand("AsAhKdKcQd"),
+            &hand("JsTs9h6h5c"),
+            &flop,
+            &cards("2d"),
+        );
+        assert_eq!(plo5.total, 703.0);
+
+        let range = OmahaRange::from("AAKKds");
+        let mut expected = EquityResult::default();
+        for hero in range.hands.iter().filter(|h| !h.intersects(&qqjj)) {
+            equitizer.set_num_threads(1);
+            expected.add_weighted(&equitizer.omaha_hand_vs_hand(hero, &qqjj, &flop, &[]), 1.0);
+        }
+        equitizer.set_num_threads(3);
+        let res = equitizer
+            .omaha_range_vs_range(&range, &OmahaRange::from("QsQhJdJc"), &flop, &[])
+            .unwrap();
+        assert_eq!(res.total, expected.total);
+        assert!((res.equity() - expected.equity()).abs() < 1e-12);
+        // a monotone flop leaves many suit-isomorphic matchups to share one enumeration
+        let (range, villain_range) = (OmahaRange::from("AAKKds"), OmahaRange::from("QQJJds"));
+        let flop = cards("2c7c8c");
+        let mut expected = EquityResult::default();
+        equitizer.set_num_threads(1);
+        for hero in &range.hands {
+            for villain in villain_range.hands.iter().filter(|h| !h.intersects(hero)) {
+                let matchup_res = equitizer.omaha_hand_vs_hand(hero, villain, &flop, &[]);
+                expected.add_weighted(&matchup_res, 1.0);
+            }
+        }
+        let res = equitizer
+            .omaha_range_vs_range(&range, &villain_range, &flop, &[])
+            .unwrap();
+        assert_eq!(res, expected);
+
+        for (board, dead) in [
+            ("2c7c", ""),
+            ("2c7c8c9c3d4d", ""),
+            ("2c7c2c", ""),
+            ("2c7c8c", "8c"),
+        ] {
+            let res =
+                equitizer.omaha_range_vs_range(&range, &villain_range, &cards(board), &cards(dead));
+            assert!(res.is_err(), "{} {}", board, dead);
+        }
+    }
+
+    #[test]
+    fn test_hi_lo_equity() {
+        let equitizer = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+        let hand = |abbr: &str| OmahaHand::parse(abbr).unwrap();
+        let (a2kk, a2qq) = (hand("As2sKdKc"), hand("Ah2hQdQc"));
+
+        // kings take the high half and the same 8-4-3-2-A low quarters the low half
+        let river = cards("3c4d8hKs9c");
+        let res = equitizer.omaha_hi_lo_hand_vs_hand(&a2kk, &a2qq, &river, &[]);
+        assert_eq!(res, HiLoEquityResult::new(2, 1, 0, 1));
+        assert_eq!(res.equity(), 0.75);
+
+        // no qualifying low, so the high hand scoops
+        let river = cards("3c9d8hKsTc");
+        let res = equitizer.omaha_hi_lo_hand_vs_hand(&a2kk, &a2qq, &river, &[]);
+        assert_eq!(res, HiLoEquityResult::new(4, 0, 1, 1));
+
+        // the shares of both sides add up to the whole pot on every runout
+        let flop = cards("3c4d9h");
+        let res = equitizer.omaha_hi_lo_hand_vs_hand(&a2kk, &a2qq, &flop, &[]);
+        let swapped = equitizer.omaha_hi_lo_hand_vs_hand(&a2qq, &a2kk, &flop, &[]);
+        assert_eq!(res.total, 820.0);
+        assert_eq!(res.high + swapped.high + res.low + swapped.low, res.total);
+        assert_eq!(res.low, swapped.low);
+        assert!(res.scoops > 0.0 && res.scoop_freq() < res.high_share());
+
+        // seven-card hi-lo: villain's 8-5-4-3-2 takes the low half, hero's ace high the high
+        let (aks, c23) = (combo("AsKs"), combo("2c3c"));
+        let river = cards("4d5h8c9dTs");
+        let res = equitizer.hi_lo_hand_vs_hand(aks, c23, &river, &[]);
+        assert_eq!(res, HiLoEquityResult::new(2, 0, 0, 1));
+
+        let turn = cards("4d5h8c9d");
+        let res = equitizer.hi_lo_hand_vs_hand(aks, c23, &turn, &[]);
+        let swapped = equitizer.hi_lo_hand_vs_hand(c23, aks, &turn, &[]);
+        assert_eq!(res.total, 44.0);
+        assert_eq!(res.equity() + swapped.equity(), 1.0);
+        // only an A or a 6 gives villain both a straight and the best low: 3 + 4 cards
+        assert_eq!(swapped.scoops, 7.0);
+    }
+
+    fn short_deck_hand_ranker() -> &'static HandRanker {
+        static HAND_RANKER: OnceLock<HandRanker> = OnceLock::new();
+        HAND_RANKER.get_or_init(HandRanker::generate_short_deck)
+    }
+
+    #[test]
+    fn test_short_deck() {
+        let equitizer =
+            Equitizer::with_cache(short_deck_hand_ranker(), Box::new(MemoryCache::new()));
+
+        let flop = cards("Td9s6c");
+        let res = equitizer.hand_vs_hand_on_board(combo("AsKs"), combo("QdQc"), &flop);
+        assert_eq!(res.total, 406.0);
+
+        // A-6-7-8-9 is a straight, which only loses to a paired river: 3 sevens, 3 eights,
+        // 3 nines and the last king out of 28 cards
+        let res = equitizer.hand_vs_hand_on_board(combo("Ac6d"), combo("KdKc"), &cards("7h8c9sKh"));
+        assert_eq!(res, EquityResult::new(18, 0, 10));
+
+        // 22 is not in the deck
+        let combo_equities = equitizer.range_vs_range_by_combo(
+            &PureRange::from("AKs,22"),
+            &PureRange::from("QQ"),
+            &flop,
+            &[],
+        );
+        assert_eq!(combo_equities.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Card not in the deck")]
+    fn test_short_deck_preflop_card_outside_deck() {
+        let equitizer =
+            Equitizer::with_cache(short_deck_hand_ranker(), Box::new(MemoryCache::new()));
+        equitizer.hand_vs_hand(combo("AsKs"), combo("2d2c"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Card not in the deck")]
+    fn test_calc_combo_vs_combo_card_outside_deck() {
+        let equitizer =
+            Equitizer::with_cache(short_deck_hand_ranker(), Box::new(MemoryCache::new()));
+        equitizer.calc_combo_vs_combo_on_board(combo("AsKs"), combo("5d5c"), &cards("Td9s6c"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicated card")]
+    fn test_calc_combo_vs_combo_duplicated_card() {
+        let equitizer = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+        equitizer.calc_combo_vs_combo(combo("AsKs"), combo("AsQd"));
+    }
+
+    #[test]
+    fn test_num_threads() {
+        let mut serial = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+        serial.set_num_threads(1);
+        let mut parallel = Equitizer::with_cache(hand_ranker(), Box::new(MemoryCache::new()));
+        parallel.set_num_threads(3);
+
+        let flop = cards("Td9s2c");
+        let (hero, villain) = (combo("AsKs"), combo("QdQc"));
+        assert_eq!(
+            serial.calc_combo_vs_combo_on_board(hero, villain, &flop),
+            parallel.calc_combo_vs_combo_on_board(hero, villain, &flop)
+        );
+
+        let lhs = PureRange::from("AKs,QJs,TT");
+        let rhs = PureRange::from("QQ,98s");
+        assert_eq!(
+            serial.range_vs_range_on_board(&lhs, &rhs, &flop),
+            parallel.range_vs_range_on_board(&lhs, &rhs, &flop)
+        );
+
+        // one instance shared by several threads
+        let turn = cards("Td9s2c3h");
+        let results = std::thread::scope(|scope| {
+            let handles = ["AsKs", "AhKh", "AdKd"].map(|abbr| {
+                scope.spawn(|| parallel.hand_vs_hand_on_board(combo(abbr), villain, &turn))
+            });
+            handles.map(|handle| handle.join().unwrap())
+        });
+        assert_eq!(
+            results[0],
+            serial.hand_vs_hand_on_board(hero, villain, &turn)
+        );
+        assert_eq!(results[1], results[0]);
+    }
 }
@@ -0,0 +1,362 @@
+use super::types::Card;
+use super::types::Combo;
+use super::types::EquityResult;
+use super::types::StackedError;
+use crate::format_stacked_err;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::sync::{Mutex, RwLock};
+
+/// Suit-canonicalised cards of a heads-up matchup. Preflop keys have an empty board and no
+/// dead cards and keep the card order produced by the preflop normalisation.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct EquityKey {
+    pub hero: Combo,
+    pub villain: Combo,
+    pub board: Vec<Card>,
+    pub dead: Vec<Card>,
+}
+
+/// Storage for the results `Equitizer` has already enumerated, shared by all its threads.
+pub trait EquityCache: Send + Sync {
+    fn get(&self, key: &EquityKey) -> Option<EquityResult>;
+
+    fn insert(&self, key: EquityKey, res: EquityResult);
+
+    /// Persists the entries inserted since the last flush. A no-op for backends without storage.
+    fn flush(&self) -> Result<(), StackedError>;
+}
+
+/// Hash map split into independently locked shards, so that concurrent lookups rarely wait.
+struct ShardedMap {
+    shards: Vec<RwLock<HashMap<EquityKey, EquityResult>>>,
+}
+
+impl ShardedMap {
+    const NUM_SHARDS: usize = 64;
+
+    fn new() -> Self {
+        Self {
+            shards: (0..Self::NUM_SHARDS)
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+        }
+    }
+
+    fn from_map(results: HashMap<EquityKey, EquityResult>) -> Self {
+        let map = Self::new();
+        for (key, res) in results {
+            map.insert(key, res);
+        }
+        map
+    }
+
+    fn shard(&self, key: &EquityKey) -> &RwLock<HashMap<EquityKey, EquityResult>> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % Self::NUM_SHARDS]
+    }
+
+    fn get(&self, key: &EquityKey) -> Option<EquityResult> {
+        self.shard(key).read().unwrap().get(key).copied()
+    }
+
+    /// Returns whether `key` was new.
+    fn insert(&self, key: EquityKey, res: EquityResult) -> bool {
+        self.shard(&key).write().unwrap().insert(key, res).is_none()
+    }
+}
+
+/// Keeps everything in memory and never touches the filesystem.
+pub struct MemoryCache {
+    results: ShardedMap,
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        Self {
+            results: ShardedMap::new(),
+        }
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EquityCache for MemoryCache {
+    fn get(&self, key: &EquityKey) -> Option<EquityResult> {
+        self.results.get(key)
+    }
+
+    fn insert(&self, key: EquityKey, res: EquityResult) {
+        self.results.insert(key, res);
+    }
+
+    fn flush(&self) -> Result<(), StackedError> {
+        Ok(())
+    }
+}
+
+/// Serves entries from a cache file without ever writing to it. New results stay in memory.
+pub struct ReadOnlyFileCache {
+    results: ShardedMap,
+}
+
+impl ReadOnlyFileCache {
+    pub fn open(filename: &str) -> Result<Self, StackedError> {
+        let content = match std::fs::read_to_string(filename) {
+            Ok(content) => content,
+            Err(e) => return format_stacked_err!("failed to read {:?}: {}", filename, e),
+        };
+        let results = parse_cache_file(&content)
+            .map_err(|e| e.chain(format!("ReadOnlyFileCache::open({:?})", filename)))?;
+        warn_skipped_lines(filename, &content);
+
+        Ok(Self {
+            results: ShardedMap::from_map(results),
+        })
+    }
+}
+
+impl EquityCache for ReadOnlyFileCache {
+    fn get(&self, key: &EquityKey) -> Option<EquityResult> {
+        self.results.get(key)
+    }
+
+    fn insert(&self, key: EquityKey, res: EquityResult) {
+        self.results.insert(key, res);
+    }
+
+    fn flush(&self) -> Result<(), StackedError> {
+        Ok(())
+    }
+}
+
+/// Loads a cache file if it exists and appends new entries to it on [`EquityCache::flush`].
+pub struct AppendFileCache {
+    filename: String,
+    results: ShardedMap,
+    pending: Mutex<Vec<(EquityKey, EquityResult)>>,
+}
+
+impl AppendFileCache {
+    pub const DEFAULT_FILENAME: &'static str = "data/equitizer_cache.txt";
+    /// Short-deck results, which must not mix with the 52-card ones.
+    pub const SHORT_DECK_FILENAME: &'static str = "data/equitizer_cache_short_deck.txt";
+
+    pub fn open(filename: &str) -> Result<Self, StackedError> {
+        let results = match std::fs::read_to_string(filename) {
+            Ok(content) => {
+                let results = parse_cache_file(&content)
+                    .map_err(|e| e.chain(format!("AppendFileCache::open({:?})", filename)))?;
+                warn_skipped_lines(filename, &content);
+                results
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return format_stacked_err!("failed to read {:?}: {}", filename, e),
+        };
+
+        Ok(Self {
+            filename: filename.to_string(),
+            results: ShardedMap::from_map(results),
+            pending: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+impl EquityCache for AppendFileCache {
+    fn get(&self, key: &EquityKey) -> Option<EquityResult> {
+        self.results.get(key)
+    }
+
+    fn insert(&self, key: EquityKey, res: EquityResult) {
+        if self.results.insert(key.clone(), res) {
+            self.pending.lock().unwrap().push((key, res));
+        }
+    }
+
+    fn flush(&self) -> Result<(), StackedError> {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut lines = String::new();
+        for (key, res) in pending.iter() {
+            lines.push_str(&format_cache_line(key, res));
+            lines.push('\n');
+        }
+
+        let path = std::path::Path::new(&self.filename);
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                return format_stacked_err!("failed to create {:?}: {}", dir, e);
+            }
+        }
+
+        std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(lines.as_bytes()))
+            .map_err(|e| StackedError::new(format!("failed to write {:?}: {}", path, e)))?;
+
+        pending.clear();
+        Ok(())
+    }
+}
+
+/// `hero villain wins ties losses` for preflop entries and
+/// `hero villain board dead wins ties losses` otherwise, with `-` for no cards.
+fn format_cache_line(key: &EquityKey, res: &EquityResult) -> String {
+    let counts = format!("{} {} {}", res.wins, res.ties, res.losses);
+    let hands = format!(
+        "{:?}{:?} {:?}{:?}",
+        key.hero.0, key.hero.1, key.villain.0, key.villain.1
+    );
+
+    if key.board.is_empty() && key.dead.is_empty() {
+        format!("{} {}", hands, counts)
+    } else {
+        format!(
+            "{} {} {} {}",
+            hands,
+            format_cards(&key.board),
+            format_cards(&key.dead),
+            counts
+        )
+    }
+}
+
+fn format_cards(cards: &[Card]) -> String {
+    if cards.is_empty() {
+        return "-".to_string();
+    }
+    cards.iter().map(|c| c.to_string()).collect()
+}
+
+fn parse_cards(abbr: &str) -> Result<Vec<Card>, StackedError> {
+    if abbr == "-" {
+        return Ok(Vec::new());
+    }
+    if abbr.len() % 2 != 0 || !abbr.is_ascii() {
+        return format_stacked_err!("invalid cards: {:?}", abbr);
+    }
+    (0..abbr.len())
+        .step_by(2)
+        .map(|i| Card::parse(&abbr[i..i + 2]))
+        .collect()
+}
+
+fn parse_combo(abbr: &str) -> Result<Combo, StackedError> {
+    match parse_cards(abbr)?[..] {
+        // the order is part of the key, so no Combo::new here
+        [c1, c2] => Ok(Combo(c1, c2)),
+        _ => format_stacked_err!("invalid combo: {:?}", abbr),
+    }
+}
+
+/// Whether `line` is `hero villain equity`, the format written before wins, ties and losses
+/// were kept apart. The counts cannot be recovered from the equity, so these lines are skipped
+/// and the matchups computed again on demand. Preflop matchups of the standard deck, which
+/// is all these lines hold, are served by the built-in preflop table anyway.
+fn is_equity_only_line(line: &str) -> bool {
+    line.split_whitespace().count() == 3
+}
+
+/// Tells on stderr how many lines of `filename` [`parse_cache_file`] skipped, if any.
+fn warn_skipped_lines(filename: &str, content: &str) {
+    let num_skipped = content
+        .lines()
+        .filter(|line| is_equity_only_line(line))
+        .count();
+    if num_skipped > 0 {
+        eprintln!(
+            "skipped {} lines of {:?} written before ties were kept",
+            num_skipped, filename
+        );
+    }
+}
+
+fn parse_cache_file(content: &str) -> Result<HashMap<EquityKey, EquityResult>, StackedError> {
+    let mut results = HashMap::new();
+
+    for line in content.lines() {
+        if is_equity_only_line(line) {
+            continue;
+        }
+
+        let parts = line.split_whitespace().collect::<Vec<&str>>();
+        let (board, dead, counts) = match parts.len() {
+            5 => ("-", "-", &parts[2..5]),
+            7 => (parts[2], parts[3], &parts[4..7]),
+            _ => return format_stacked_err!("Invalid line in cache file: {}", line),
+        };
+
+        let mut parsed_counts = [0; 3];
+        for (parsed_count, count) in parsed_counts.iter_mut().zip(counts) {
+            *parsed_count = match count.parse::<u64>() {
+                Ok(count) => count,
+                Err(_) => return format_stacked_err!("Invalid line in cache file: {}", line),
+            };
+        }
+
+        let stack = |e: StackedError| e.chain(format!("Invalid line in cache file: {}", line));
+        let key = EquityKey {
+            hero: parse_combo(parts[0]).map_err(stack)?,
+            villain: parse_combo(parts[1]).map_err(stack)?,
+            board: parse_cards(board).map_err(stack)?,
+            dead: parse_cards(dead).map_err(stack)?,
+        };
+        let res = EquityResult::new(parsed_counts[0], parsed_counts[1], parsed_counts[2]);
+        results.insert(key, res);
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+
+    #[test]
+    fn test_append_file_cache() {
+        let temp_path = TempPath::new("append_file_cache.txt");
+        let filename = temp_path.as_str();
+        std::fs::write(filename, "AcKc QdQh 0.4\n").unwrap();
+
+        let preflop_key = EquityKey {
+            hero: Combo(Card::parse("Ac").unwrap(), Card::parse("Kc").unwrap()),
+            villain: Combo(Card::parse("Qd").unwrap(), Card::parse("Qh").unwrap()),
+            board: Vec::new(),
+            dead: Vec::new(),
+        };
+        let river_key = EquityKey {
+            board: parse_cards("2c3d4h5s6s").unwrap(),
+            dead: parse_cards("7s").unwrap(),
+            ..preflop_key.clone()
+        };
+
+        let cache = AppendFileCache::open(filename).unwrap();
+        assert_eq!(cache.get(&preflop_key), None);
+        cache.insert(preflop_key.clone(), EquityResult::new(1, 2, 3));
+        cache.insert(river_key.clone(), EquityResult::new(0, 1, 0));
+        cache.flush().unwrap();
+
+        let cache = ReadOnlyFileCache::open(filename).unwrap();
+        assert_eq!(cache.get(&preflop_key), Some(EquityResult::new(1, 2, 3)));
+        assert_eq!(cache.get(&river_key), Some(EquityResult::new(0, 1, 0)));
+
+        assert!(is_equity_only_line("AcKc QdQh 0.4"));
+        assert!(!is_equity_only_line("AcKc QdQh 1 2 3"));
+        assert!(parse_cache_file("AcKc QdQh 0.4").unwrap().is_empty());
+        assert!(parse_cache_file("AcKc QdQh 1 2").is_err());
+        assert!(parse_cache_file("AcKc QdQx 1 2 3").is_err());
+    }
+}
@@ -1,15 +1,20 @@
+use super::types::BestHand;
 use super::types::Card;
 use super::types::HandRank;
+use super::types::PartialHand;
 use super::types::Rank;
+use super::types::StackedError;
 use super::types::Suit;
-use permutohedron::LexicalPermutation;
+use crate::format_stacked_err;
+use crate::table_gen;
+use crate::table_gen::Ranking;
+use crate::table_gen::{
+    fnv1a64, TABLE_BYTE_ORDER_MARK, TABLE_ENTRY_SIZE, TABLE_HEADER_LEN, TABLE_MAGIC, TABLE_VERSION,
+};
 use std::cmp::max;
-use std::fs::File;
-use std::io::BufReader;
-use std::io::Read;
-use std::io::Write;
+use std::sync::OnceLock;
 
-const SUIT_CNT_TABLE: [usize; 4609] = [
+static SUIT_CNT_TABLE: [usize; 4609] = [
     0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 0,
     0, 0, 0, 0, 0, 1, 0, 0, 2, 2, 2, 2, 2, 0, 0, 0, 2, 2, 2, 2, 0, 0, 0, 0, 2, 2, 2, 0, 0, 0, 0, 0,
     2, 2, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0,
@@ -160,87 +165,191 @@ const SUIT_CNT_TABLE: [usize; 4609] = [
 const POW_13_2: usize = 13 * 13;
 const POW_13_3: usize = POW_13_2 * 13;
 const POW_13_4: usize = POW_13_3 * 13;
-const POW_13_5: usize = POW_13_4 * 13;
-const POW_13_6: usize = POW_13_5 * 13;
-const POW_13_7: usize = POW_13_6 * 13;
+pub(crate) const POW_13_5: usize = POW_13_4 * 13;
+
+/// Number of multisets of 6 ranks, the size of the compact offsuited6 table.
+const NUM_RANK_MULTISETS_6: usize = binomial(13 + 6 - 1, 6);
+/// Number of multisets of 7 ranks, the size of the compact offsuited7 table.
+const NUM_RANK_MULTISETS_7: usize = binomial(13 + 7 - 1, 7);
+
+const fn binomial(n: usize, k: usize) -> usize {
+    let mut result = 1;
+    let mut i = 0;
+    while i < k {
+        result = result * (n - i) / (i + 1);
+        i += 1;
+    }
+    result
+}
+
+/// BINOMIAL[n][k] = n choose k, for the combinatorial number system.
+static BINOMIAL: [[usize; 8]; 19] = {
+    let mut table = [[0; 8]; 19];
+    let mut n = 0;
+    while n < 19 {
+        let mut k = 0;
+        while k < 8 && k <= n {
+            table[n][k] = binomial(n, k);
+            k += 1;
+        }
+        n += 1;
+    }
+    table
+};
+
+/// Seven-card table of a rank-only five-card table indexed like offsuited5: for every
+/// multiset of 7 ranks, at [`rank_multiset_index`], the best of the 21 ways to drop two cards.
+pub(crate) fn best_of_7<T: Copy + Ord>(table5: &[T], worst: T) -> Vec<T> {
+    let mut table7 = vec![worst; NUM_RANK_MULTISETS_7];
+    let mut rank_values = [0; 7];
+
+    loop {
+        let mut best = worst;
+        for skip0 in 0..7 {
+            for skip1 in skip0 + 1..7 {
+                let mut hash5 = 0;
+                for (i, &r) in rank_values.iter().enumerate() {
+                    if i != skip0 && i != skip1 {
+                        hash5 = hash5 * 13 + r;
+                    }
+                }
+                best = max(best, table5[hash5]);
+            }
+        }
+        table7[rank_multiset_index(rank_values)] = best;
+
+        // next non-decreasing sequence
+        let Some(i) = (0..7).rev().find(|&i| rank_values[i] < 12) else {
+            break;
+        };
+        let r = rank_values[i] + 1;
+        rank_values[i..].fill(r);
+    }
+
+    table7
+}
+
+/// Position of the multiset of `rank_values` among all multisets of N ranks (N <= 7): the
+/// sorted ranks r0 <= .. <= r(N-1) become the distinct values r_i + i, ranked by the
+/// combinatorial number system.
+pub(crate) fn rank_multiset_index<const N: usize>(mut rank_values: [usize; N]) -> usize {
+    rank_values.sort_unstable();
+
+    let mut index = 0;
+    for (i, r) in rank_values.into_iter().enumerate() {
+        index += BINOMIAL[r + i][i + 1];
+    }
+    index
+}
 
 pub struct HandRanker {
     suited: Vec<HandRank>,
     offsuited5: Vec<HandRank>,
+    offsuited6: Vec<HandRank>,
     offsuited7: Vec<HandRank>,
+    short_deck: bool,
 }
 
 impl HandRanker {
-    pub fn new(mut data_dir: &str) -> Result<Self, ()> {
+    const OFFSUITED5_CHECKSUM: i64 = 156362476943832;
+    const SUITED_CHECKSUM: i64 = 217636760248;
+
+    /// Number of hands per chunk of [`HandRanker::evaluate_batch`].
+    pub const BATCH_SIZE: usize = 64;
+
+    /// Loads the tables from `data_dir` ("data" when empty), or generates them and saves them
+    /// there when they are missing or damaged.
+    pub fn new(mut data_dir: &str) -> Result<Self, StackedError> {
         if data_dir.is_empty() {
             data_dir = "data";
         }
 
-        std::fs::create_dir_all(data_dir).unwrap();
+        if let Ok(result) = Self::load_from(data_dir) {
+            return Ok(result);
+        }
 
-        for c in 0..7 {
-            for d in 0..7 {
-                for h in 0..7 {
-                    for s in 0..7 {
-                        if c + d + h + s > 6 {
-                            continue;
-                        }
-                        if c + d + h + s < 5 {
-                            continue;
-                        }
+        let result = Self::generate();
+        result
+            .save_to(data_dir)
+            .map_err(|e| e.chain(format!("HandRanker::new({:?})", data_dir)))?;
 
-                        let suit_cnt_hash = c | (d << 3) | (h << 6) | (s << 9);
-                        let suit_cnt = SUIT_CNT_TABLE[suit_cnt_hash];
-
-                        if c >= 5 {
-                            if suit_cnt != Suit::CLUB.as_usize() + 1 {
-                                panic!("suited7 error");
-                            }
-                        } else if d >= 5 {
-                            if suit_cnt != Suit::DIAMOND.as_usize() + 1 {
-                                panic!("suited7 error");
-                            }
-                        } else if h >= 5 {
-                            if suit_cnt != Suit::HEART.as_usize() + 1 {
-                                panic!("suited7 error");
-                            }
-                        } else if s >= 5 {
-                            if suit_cnt != Suit::SPADE.as_usize() + 1 {
-                                panic!("suited7 error");
-                            }
-                        } else {
-                            if suit_cnt != 0 {
-                                panic!("suited7 error");
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        Ok(result)
+    }
+
+    /// Builds every table in memory, without any IO.
+    pub fn generate() -> Self {
+        let mut result = Self::empty();
+        result.calc_data();
+        result.calc_offsuited6();
+        result.calc_offsuited7();
+        result
+    }
+
+    /// Uses the tables that build.rs generated into the binary, without any IO. Only the six
+    /// and seven card tables are derived at runtime.
+    #[cfg(feature = "embedded")]
+    pub fn embedded() -> Self {
+        static OFFSUITED5: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/offsuited5.bin"));
+        static SUITED: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/suited.bin"));
+
+        let mut result = Self::empty();
+        decode_table(
+            OFFSUITED5,
+            &mut result.offsuited5,
+            Self::OFFSUITED5_CHECKSUM,
+        )
+        .expect("embedded offsuited5 table");
+        decode_table(SUITED, &mut result.suited, Self::SUITED_CHECKSUM)
+            .expect("embedded suited table");
+        result.calc_offsuited6();
+        result.calc_offsuited7();
+        result
+    }
 
-        let mut result = Self {
+    fn empty() -> Self {
+        check_suit_cnt_table();
+
+        Self {
             suited: vec![HandRank::ERROR; 1 << 13],
             offsuited5: vec![HandRank::ERROR; POW_13_5],
-            offsuited7: vec![HandRank::ERROR; POW_13_7],
-        };
-
-        match result.load_data(data_dir) {
-            Ok(_) => Ok(result),
-            Err(_) => {
-                println!("failed to load data from `{}`", data_dir);
-                result.calc_data();
-                result.save_data(data_dir).unwrap();
-                Ok(result)
-            }
+            offsuited6: vec![HandRank::ERROR; NUM_RANK_MULTISETS_6],
+            offsuited7: vec![HandRank::ERROR; NUM_RANK_MULTISETS_7],
+            short_deck: false,
         }
     }
 
+    /// Ranks short-deck (6+) hold'em hands, built in memory like [`HandRanker::generate`]. The
+    /// deck has no 2 to 5, A-6-7-8-9 is the lowest straight and a flush beats a full house.
+    ///
+    /// Its hand ranks only compare with each other: they are numbered from 0 in short-deck
+    /// order, so [`HandRank::category`] and [`HandRank::description`] do not apply to them.
+    pub fn generate_short_deck() -> Self {
+        let mut result = Self::empty();
+        result.short_deck = true;
+        result.calc_data();
+        result.calc_offsuited6();
+        result.calc_offsuited7();
+        result
+    }
+
+    pub fn is_short_deck(&self) -> bool {
+        self.short_deck
+    }
+
+    /// The cards in play: all 52, or the 36 from 6 up for short deck.
+    pub fn deck(&self) -> Vec<Card> {
+        (0..52)
+            .map(Card::from_value)
+            .filter(|&card| self.in_deck(card))
+            .collect()
+    }
+
+    pub fn in_deck(&self, card: Card) -> bool {
+        !self.short_deck || card.rank().value >= Rank::VALUE_6
+    }
+
     pub fn get7_offsuited(&self, ranks: [Rank; 7]) -> HandRank {
-        let mut hash = 0;
-        for r in ranks {
-            hash = hash * 13 + r.as_usize();
-        }
-        self.offsuited7[hash]
+        self.offsuited7[rank_multiset_index(ranks.map(|r| r.as_usize()))]
     }
 
     pub fn get7(&self, cards: [Card; 7]) -> HandRank {
@@ -274,12 +383,135 @@ impl HandRanker {
                 suited_hash[suits[i].clone().as_usize()] |= 1 << ranks[i].as_usize();
             }
             let s = SUIT_CNT_TABLE[suit_cnt_hash] - 1;
-            return self.suited[suited_hash[s]].clone();
+            self.suited[suited_hash[s]]
         } else {
             self.get7_offsuited(ranks)
         }
     }
 
+    /// Hand rank of the 5 to 7 cards whose bits are set in `mask`, see [`Card::mask`]. Panics
+    /// on any other number of cards and on bits above the last card.
+    pub fn evaluate(&self, mask: u64) -> HandRank {
+        assert!(mask >> 52 == 0, "not a card mask: {:#x}", mask);
+        self.evaluate_partial(&PartialHand::from_mask(mask))
+    }
+
+    /// Hand rank of a [`PartialHand`] of 5 to 7 cards. Panics on any other hand.
+    pub fn evaluate_partial(&self, hand: &PartialHand) -> HandRank {
+        let num_cards = hand.num_cards();
+        assert!((5..=7).contains(&num_cards), "{} cards", num_cards);
+        assert!(hand.mask >> 52 == 0, "not a card mask: {:#x}", hand.mask);
+
+        if num_cards == 7 {
+            return match table_index7(hand) {
+                (true, index) => self.suited[index],
+                (false, index) => self.offsuited7[index],
+            };
+        }
+
+        if SUIT_CNT_TABLE[hand.suit_cnt_hash] != 0 {
+            let s = SUIT_CNT_TABLE[hand.suit_cnt_hash] - 1;
+            return self.suited[hand.suited_hashes[s]];
+        }
+
+        // the bits come out by increasing card value, so the ranks are sorted
+        let mut mask = hand.mask;
+        let mut hash = 0;
+        let mut index = 0;
+        for i in 0..num_cards {
+            let r = (mask.trailing_zeros() >> 2) as usize;
+            mask &= mask - 1;
+            hash = hash * 13 + r;
+            index += BINOMIAL[r + i][i + 1];
+        }
+        if num_cards == 6 {
+            self.offsuited6[index]
+        } else {
+            self.offsuited5[hash]
+        }
+    }
+
+    /// Hand ranks of many seven-card hands, `out[i]` for `hands[i]`.
+    ///
+    /// Works on chunks of [`HandRanker::BATCH_SIZE`] hands: a first loop turns every hand into a
+    /// table index, a second one does the lookups. The lookups of a chunk then neither depend
+    /// on each other nor share a loop with branches, so they can be issued back to back.
+    pub fn evaluate_batch(&self, hands: &[PartialHand], out: &mut [HandRank]) {
+        assert_eq!(hands.len(), out.len());
+
+        for (hands, out) in hands
+            .chunks(Self::BATCH_SIZE)
+            .zip(out.chunks_mut(Self::BATCH_SIZE))
+        {
+            self.evaluate_chunk(hands.iter().copied(), out);
+        }
+    }
+
+    /// Hand ranks of `board` (five cards) with each of `holes` (two cards), `out[i]` for
+    /// `holes[i]`. A hole that shares a card with the board gets [`HandRank::ERROR`].
+    pub fn evaluate_board_batch(
+        &self,
+        board: &PartialHand,
+        holes: &[PartialHand],
+        out: &mut [HandRank],
+    ) {
+        assert_eq!(holes.len(), out.len());
+
+        for (holes, out) in holes
+            .chunks(Self::BATCH_SIZE)
+            .zip(out.chunks_mut(Self::BATCH_SIZE))
+        {
+            let hands = holes.iter().map(|hole| {
+                if hole.mask & board.mask != 0 {
+                    PartialHand::EMPTY
+                } else {
+                    hole.union(*board)
+                }
+            });
+            self.evaluate_chunk(hands, out);
+        }
+    }
+
+    fn evaluate_chunk(&self, hands: impl Iterator<Item = PartialHand>, out: &mut [HandRank]) {
+        let mut indices = [(false, 0); Self::BATCH_SIZE];
+        for (index, hand) in indices.iter_mut().zip(hands) {
+            *index = table_index7(&hand);
+        }
+
+        for (hand_rank, &(is_flush, index)) in out.iter_mut().zip(&indices) {
+            *hand_rank = if is_flush {
+                self.suited[index]
+            } else {
+                self.offsuited7[index]
+            };
+        }
+    }
+
+    pub fn get6_offsuited(&self, ranks: [Rank; 6]) -> HandRank {
+        self.offsuited6[rank_multiset_index(ranks.map(|r| r.as_usize()))]
+    }
+
+    pub fn get6(&self, cards: [Card; 6]) -> HandRank {
+        let ranks = cards.map(|card| card.rank());
+        let suits = cards.map(|card| card.suit());
+
+        let mut suit_cnt_hash = 0;
+        for s in &suits {
+            suit_cnt_hash += 1 << (s.as_usize() * 3);
+        }
+
+        if SUIT_CNT_TABLE[suit_cnt_hash] != 0 {
+            let mut suited_hash = [0; 4];
+            for i in 0..6 {
+                suited_hash[suits[i].as_usize()] |= 1 << ranks[i].as_usize();
+            }
+            let s = SUIT_CNT_TABLE[suit_cnt_hash] - 1;
+            self.suited[suited_hash[s]]
+        } else {
+            self.get6_offsuited(ranks)
+        }
+    }
+
     pub fn get5_offsuited(&self, ranks: [Rank; 5]) -> HandRank {
         let mut hash = 0;
         for r in ranks {
@@ -289,13 +521,16 @@ impl HandRanker {
     }
 
     pub fn get5(&self, cards: [Card; 5]) -> HandRank {
-        let bitand_suit_value = cards[0].suit().value
-            & cards[1].suit().value
-            & cards[2].suit().value
-            & cards[3].suit().value
-            & cards[4].suit().value;
+        // compare whole suits: masking suit values misses clubs, whose value is 0
+        let is_flush = cards.iter().all(|card| card.suit() == cards[0].suit());
+        debug_assert_eq!(
+            is_flush,
+            flush_suit(&cards).is_some(),
+            "get5 flush detection disagrees with the suit count of {:?}",
+            cards
+        );
 
-        if bitand_suit_value == 0 {
+        if !is_flush {
             // offsuited
             let ranks = [
                 cards[0].rank(),
@@ -308,455 +543,1103 @@ impl HandRanker {
         } else {
             // suited
             let mut suited_hash = 0;
-            for i in 0..5 {
-                suited_hash |= 1 << cards[i].rank().as_usize();
+            for card in &cards {
+                suited_hash |= 1 << card.rank().as_usize();
             }
             self.suited[suited_hash]
         }
     }
 
-    fn do_process(
-        &mut self,
-        hand_rank: &mut HandRank,
-        offsuited_details: &mut Vec<([i32; 5], HandRank)>,
-        need_flush: bool,
-        r0_value: i32,
-        r1_value: i32,
-        r2_value: i32,
-        r3_value: i32,
-        r4_value: i32,
-    ) {
-        // println!(
-        //     "do_process: {:?}",
-        //     (r0_value, r1_value, r2_value, r3_value, r4_value)
-        // );
-        if need_flush {
-            let hh = (1 << r0_value)
-                | (1 << r1_value)
-                | (1 << r2_value)
-                | (1 << r3_value)
-                | (1 << r4_value);
-            self.suited[hh] = hand_rank.clone();
-            hand_rank.inc();
-        } else {
-            let card_rank_values = [r0_value, r1_value, r2_value, r3_value, r4_value];
-            offsuited_details.push((card_rank_values, hand_rank.clone()));
-            hand_rank.inc();
+    /// Omaha hand rank: the best [`HandRanker::get5`] of exactly two of the `hole` cards (four
+    /// for PLO4, five for PLO5) and exactly three of the `board` cards.
+    pub fn get_omaha(&self, hole: &[Card], board: [Card; 5]) -> HandRank {
+        let mut best = HandRank::ERROR;
+        for b0 in 0..5 {
+            for b1 in b0 + 1..5 {
+                for b2 in b1 + 1..5 {
+                    for h0 in 0..hole.len() {
+                        for h1 in h0 + 1..hole.len() {
+                            best = max(
+                                best,
+                                self.get5([hole[h0], hole[h1], board[b0], board[b1], board[b2]]),
+                            );
+                        }
+                    }
+                }
+            }
         }
+        best
     }
 
-    fn process_no_pair(
-        &mut self,
-        hand_rank: &mut HandRank,
-        offsuited_details: &mut Vec<([i32; 5], HandRank)>,
-        need_flush: bool,
-        need_straight: bool,
-    ) {
-        if need_straight {
-            self.do_process(
-                hand_rank,
-                offsuited_details,
-                need_flush,
-                Rank::VALUE_2,
-                Rank::VALUE_3,
-                Rank::VALUE_4,
-                Rank::VALUE_5,
-                Rank::VALUE_A,
-            );
-            for r_value in Rank::VALUE_6..=Rank::VALUE_A {
-                self.do_process(
-                    hand_rank,
-                    offsuited_details,
-                    need_flush,
-                    r_value - 4,
-                    r_value - 3,
-                    r_value - 2,
-                    r_value - 1,
-                    r_value,
-                );
+    /// Picks the five cards of `hole` and `board` that make [`HandRanker::get7`]'s hand rank.
+    /// When several choices tie, the one with the fewest hole cards wins.
+    pub fn best_hand(&self, hole: [Card; 2], board: [Card; 5]) -> BestHand {
+        let cards = [
+            hole[0], hole[1], board[0], board[1], board[2], board[3], board[4],
+        ];
+
+        let mut best: Option<(HandRank, usize, [Card; 5])> = None;
+        for skip0 in 0..7 {
+            for skip1 in skip0 + 1..7 {
+                let mut five = [cards[0]; 5];
+                let mut pos = 0;
+                for (i, &card) in cards.iter().enumerate() {
+                    if i != skip0 && i != skip1 {
+                        five[pos] = card;
+                        pos += 1;
+                    }
+                }
+
+                let hand_rank = self.get5(five);
+                let num_hole_cards = five.iter().filter(|card| hole.contains(card)).count();
+                if best.is_none_or(|(best_rank, best_num_hole_cards, _)| {
+                    hand_rank > best_rank
+                        || (hand_rank == best_rank && num_hole_cards < best_num_hole_cards)
+                }) {
+                    best = Some((hand_rank, num_hole_cards, five));
+                }
             }
+        }
+
+        let (hand_rank, _, five) = best.unwrap();
+
+        // sort by the order of significance of the hand rank
+        let mut used = [false; 5];
+        let ranks = if self.short_deck {
+            short_deck_rank_values()[hand_rank.value() as usize].map(Rank::from_value)
         } else {
-            for r4_value in Rank::VALUE_2..=Rank::VALUE_A {
-                for r3_value in Rank::VALUE_2..r4_value {
-                    for r2_value in Rank::VALUE_2..r3_value {
-                        for r1_value in Rank::VALUE_2..r2_value {
-                            for r0_value in Rank::VALUE_2..r1_value {
-                                let mut is_straight = false;
-                                if r3_value - r0_value == 3 {
-                                    if r4_value == r3_value + 1
-                                        || (r4_value == Rank::VALUE_A && r0_value == Rank::VALUE_2)
-                                    {
-                                        is_straight = true;
-                                    }
-                                }
-
-                                if !is_straight {
-                                    self.do_process(
-                                        hand_rank,
-                                        offsuited_details,
-                                        need_flush,
-                                        r0_value,
-                                        r1_value,
-                                        r2_value,
-                                        r3_value,
-                                        r4_value,
-                                    );
-                                }
-                            }
-                        }
+            hand_rank.ranks()
+        };
+        let cards = ranks.map(|rank| {
+            let i = (0..5)
+                .find(|&i| !used[i] && five[i].rank() == rank)
+                .unwrap();
+            used[i] = true;
+            five[i]
+        });
+
+        BestHand {
+            hand_rank,
+            cards,
+            hole_cards: cards
+                .iter()
+                .copied()
+                .filter(|card| hole.contains(card))
+                .collect(),
+        }
+    }
+
+    /// Derives the six-card table from offsuited5: for every multiset of 6 ranks, at
+    /// [`rank_multiset_index`], the best of the six ways to drop a card.
+    fn calc_offsuited6(&mut self) {
+        let mut rank_values = [0; 6];
+
+        loop {
+            let mut hand_rank = HandRank::ERROR;
+            for skip in 0..6 {
+                let mut hash5 = 0;
+                for (i, &r) in rank_values.iter().enumerate() {
+                    if i != skip {
+                        hash5 = hash5 * 13 + r;
                     }
                 }
+                hand_rank = max(hand_rank, self.offsuited5[hash5]);
             }
+            self.offsuited6[rank_multiset_index(rank_values)] = hand_rank;
+
+            // next non-decreasing sequence
+            let Some(i) = (0..6).rev().find(|&i| rank_values[i] < 12) else {
+                break;
+            };
+            let r = rank_values[i] + 1;
+            rank_values[i..].fill(r);
         }
     }
 
+    /// Derives the seven-card table from offsuited5, see [`best_of_7`].
+    fn calc_offsuited7(&mut self) {
+        self.offsuited7 = best_of_7(&self.offsuited5, HandRank::ERROR);
+    }
+
+    /// Fills the five-card and flush tables from the standard or short-deck hand patterns of
+    /// [`table_gen`].
     pub fn calc_data(&mut self) {
-        if HandRank::ERROR.value() >= 0 {
-            panic!("ERROR must be negative");
+        let ranking = if self.short_deck {
+            Ranking::ShortDeck
+        } else {
+            Ranking::Standard
+        };
+        let tables = table_gen::generate(ranking);
+        for (table, generated) in [
+            (&mut self.suited, tables.suited),
+            (&mut self.offsuited5, tables.offsuited5),
+        ] {
+            for (v, generated) in table.iter_mut().zip(generated) {
+                *v = HandRank::from_value(generated as i32);
+            }
         }
+    }
 
-        let mut hand_rank = HandRank::from_value(0);
-        let mut offsuited_details = Vec::new();
+    /// Writes offsuited5.bin and suited.bin into `data_dir`, creating it if needed. The six
+    /// and seven card tables are derived on load and not saved.
+    pub fn save_to(&self, data_dir: &str) -> Result<(), StackedError> {
+        if self.short_deck {
+            return format_stacked_err!("short-deck tables are not saved");
+        }
 
-        self.process_no_pair(&mut hand_rank, &mut offsuited_details, false, false);
+        if let Err(e) = std::fs::create_dir_all(data_dir) {
+            return format_stacked_err!("failed to create {:?}: {}", data_dir, e);
+        }
 
-        println!(
-            "finished process high cards, hand_rank={:?}, offsuited_details.len(): {}",
-            hand_rank,
-            offsuited_details.len()
+        for (name, table) in [("offsuited5", &self.offsuited5), ("suited", &self.suited)] {
+            let filename = format!("{}/{}.bin", data_dir, name);
+            if let Err(e) = std::fs::write(&filename, encode_table(table)) {
+                return format_stacked_err!("failed to write {:?}: {}", filename, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the tables written by [`HandRanker::save_to`]. A file with an unexpected header,
+    /// size, checksum or content is refused with an error naming the problem.
+    pub fn load_from(data_dir: &str) -> Result<Self, StackedError> {
+        let mut result = Self::empty();
+
+        for (name, table, checksum) in [
+            (
+                "offsuited5",
+                &mut result.offsuited5,
+                Self::OFFSUITED5_CHECKSUM,
+            ),
+            ("suited", &mut result.suited, Self::SUITED_CHECKSUM),
+        ] {
+            let filename = format!("{}/{}.bin", data_dir, name);
+            let stack = |e: StackedError| {
+                e.chain(format!(
+                    "HandRanker::load_from({:?}): {:?}",
+                    data_dir, filename
+                ))
+            };
+
+            let bytes = match std::fs::read(&filename) {
+                Ok(bytes) => bytes,
+                Err(e) => return format_stacked_err!("failed to read {:?}: {}", filename, e),
+            };
+            decode_table(&bytes, table, checksum).map_err(stack)?;
+        }
+        result.calc_offsuited6();
+        result.calc_offsuited7();
+
+        Ok(result)
+    }
+}
+
+fn encode_table(table: &[HandRank]) -> Vec<u8> {
+    table_gen::encode_table(&table.iter().map(|v| v.value() as i16).collect::<Vec<_>>())
+}
+
+/// Fills `table` from a file written by [`encode_table`], checking the header against the
+/// table's size, the payload against its checksum, and the content against `content_checksum`,
+/// the sum of index times hand rank of the one correct table.
+fn decode_table(
+    bytes: &[u8],
+    table: &mut [HandRank],
+    content_checksum: i64,
+) -> Result<(), StackedError> {
+    if bytes.len() < TABLE_HEADER_LEN || &bytes[0..4] != TABLE_MAGIC {
+        return format_stacked_err!("not a hand ranker table");
+    }
+
+    let read_u32 = |pos: usize| u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap());
+
+    let version = read_u32(4);
+    if version != TABLE_VERSION {
+        return format_stacked_err!(
+            "unsupported table version: {}, expected {}",
+            version,
+            TABLE_VERSION
+        );
+    }
+
+    let byte_order_mark = read_u32(8);
+    if byte_order_mark != TABLE_BYTE_ORDER_MARK {
+        return format_stacked_err!("unexpected byte order mark: {:#010x}", byte_order_mark);
+    }
+
+    let entry_size = read_u32(12);
+    if entry_size != TABLE_ENTRY_SIZE {
+        return format_stacked_err!("unsupported entry size: {}", entry_size);
+    }
+
+    let num_entries = read_u32(16) as usize;
+    if num_entries != table.len() {
+        return format_stacked_err!(
+            "table has {} entries, expected {}",
+            num_entries,
+            table.len()
         );
+    }
 
+    let payload = &bytes[TABLE_HEADER_LEN..];
+    if payload.len() != num_entries * TABLE_ENTRY_SIZE as usize {
+        return format_stacked_err!(
+            "table payload has {} bytes, expected {}",
+            payload.len(),
+            num_entries * TABLE_ENTRY_SIZE as usize
+        );
+    }
+
+    let checksum = u64::from_le_bytes(bytes[20..28].try_into().unwrap());
+    if fnv1a64(payload) != checksum {
+        return format_stacked_err!("table checksum mismatch");
+    }
+
+    let mut sum: i64 = 0;
+    for (i, (v, chunk)) in table.iter_mut().zip(payload.chunks_exact(2)).enumerate() {
+        // i16, not u16, or ERROR would come back as 65535
+        let value = i16::from_le_bytes([chunk[0], chunk[1]]);
+        if value != HandRank::ERROR.value() as i16
+            && !(0..HandRank::NUM.value() as i16).contains(&value)
         {
-            // one pair
-            for rr_value in Rank::VALUE_2..=Rank::VALUE_A {
-                for r2_value in Rank::VALUE_2..=Rank::VALUE_A {
-                    if r2_value == rr_value {
-                        continue;
-                    }
+            return format_stacked_err!("invalid hand rank {} at entry {}", value, i);
+        }
+        *v = HandRank::from_value(value as i32);
+        sum += (value as i64) * (i as i64);
+    }
 
-                    for r1_value in Rank::VALUE_2..r2_value {
-                        if r1_value == rr_value {
-                            continue;
-                        }
+    if sum != content_checksum {
+        return format_stacked_err!("table content differs from the expected one: {}", sum);
+    }
+
+    Ok(())
+}
+
+/// Rank values of every short-deck hand rank, like [`HandRank::ranks`] for the standard ones.
+fn short_deck_rank_values() -> &'static [[i32; 5]] {
+    static RANK_VALUES: OnceLock<Vec<[i32; 5]>> = OnceLock::new();
+
+    RANK_VALUES.get_or_init(|| {
+        table_gen::hand_patterns(Ranking::ShortDeck)
+            .into_iter()
+            .map(|(ranks, _)| ranks)
+            .collect()
+    })
+}
+
+/// Table and index of a seven-card hand: `suited` when the flag is set, `offsuited7`
+/// otherwise. Anything but seven cards maps to the `ERROR` entry `suited[0]`.
+fn table_index7(hand: &PartialHand) -> (bool, usize) {
+    if hand.mask.count_ones() != 7 {
+        return (true, 0);
+    }
+
+    let flush_suit = SUIT_CNT_TABLE[hand.suit_cnt_hash];
+    if flush_suit != 0 {
+        return (true, hand.suited_hashes[flush_suit - 1]);
+    }
+
+    // the bits come out by increasing card value, so the ranks are sorted
+    let mut mask = hand.mask;
+    let mut index = 0;
+    for i in 0..7 {
+        let r = (mask.trailing_zeros() >> 2) as usize;
+        mask &= mask - 1;
+        index += BINOMIAL[r + i][i + 1];
+    }
+    (false, index)
+}
+
+/// Suit value of the flush among `cards` by counting suits through `SUIT_CNT_TABLE`, as
+/// [`HandRanker::get7`] does.
+fn flush_suit(cards: &[Card]) -> Option<usize> {
+    let suit_cnt_hash = cards
+        .iter()
+        .map(|card| 1 << (card.suit().as_usize() * 3))
+        .sum::<usize>();
+    SUIT_CNT_TABLE[suit_cnt_hash].checked_sub(1)
+}
 
-                        for r0_value in Rank::VALUE_2..r1_value {
-                            if r0_value == rr_value {
-                                continue;
-                            }
+/// Verifies that SUIT_CNT_TABLE names the flush suit of every 5 to 7 card suit count.
+fn check_suit_cnt_table() {
+    for c in 0..7 {
+        for d in 0..7 {
+            for h in 0..7 {
+                for s in 0..7 {
+                    if c + d + h + s > 6 {
+                        continue;
+                    }
+                    if c + d + h + s < 5 {
+                        continue;
+                    }
 
-                            let card_ranks = [rr_value, rr_value, r0_value, r1_value, r2_value];
+                    let suit_cnt_hash = c | (d << 3) | (h << 6) | (s << 9);
+                    let suit_cnt = SUIT_CNT_TABLE[suit_cnt_hash];
 
-                            offsuited_details.push((card_ranks, hand_rank.clone()));
-                            hand_rank.inc();
+                    if c >= 5 {
+                        if suit_cnt != Suit::CLUB.as_usize() + 1 {
+                            panic!("suited7 error");
+                        }
+                    } else if d >= 5 {
+                        if suit_cnt != Suit::DIAMOND.as_usize() + 1 {
+                            panic!("suited7 error");
+                        }
+                    } else if h >= 5 {
+                        if suit_cnt != Suit::HEART.as_usize() + 1 {
+                            panic!("suited7 error");
+                        }
+                    } else if s >= 5 {
+                        if suit_cnt != Suit::SPADE.as_usize() + 1 {
+                            panic!("suited7 error");
+                        }
+                    } else {
+                        if suit_cnt != 0 {
+                            panic!("suited7 error");
                         }
                     }
                 }
             }
         }
+    }
+}
 
-        println!(
-            "finished process pair, hand_rank={:?}, offsuited_details.len(): {}",
-            hand_rank,
-            offsuited_details.len()
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+    use crate::types::HandCategory;
+    use crate::types::OmahaHand;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+    use std::collections::HashMap;
+    use std::sync::OnceLock;
+
+    fn hand_ranker() -> &'static HandRanker {
+        static HAND_RANKER: OnceLock<HandRanker> = OnceLock::new();
+        HAND_RANKER.get_or_init(HandRanker::generate)
+    }
 
-        // two pair
-        {
-            for rr1 in Rank::VALUE_2..=Rank::VALUE_A {
-                for rr0 in Rank::VALUE_2..rr1 {
-                    for r in Rank::VALUE_2..=Rank::VALUE_A {
-                        if r == rr1 || r == rr0 {
-                            continue;
-                        }
+    /// Slow evaluator written straight from the rules: the category and the card ranks in
+    /// order of significance, which together identify a [`HandRank`].
+    fn reference_get5(cards: [Card; 5]) -> (HandCategory, [i32; 5]) {
+        let mut counts = [0; 13];
+        for card in &cards {
+            counts[card.rank().as_usize()] += 1;
+        }
 
-                        let card_ranks = [rr0, rr0, rr1, rr1, r];
-                        offsuited_details.push((card_ranks, hand_rank.clone()));
-                        hand_rank.inc();
-                    }
-                }
+        // (count, rank) groups, biggest group first, then highest rank first
+        let mut groups = (0..13)
+            .filter(|&r| counts[r] > 0)
+            .map(|r| (counts[r], r as i32))
+            .collect::<Vec<_>>();
+        groups.sort_by(|a, b| b.cmp(a));
+
+        let mut ranks = [0; 5];
+        let mut pos = 0;
+        for &(count, r) in &groups {
+            for _ in 0..count {
+                ranks[pos] = r;
+                pos += 1;
             }
         }
 
-        println!(
-            "finished process two pair, hand_rank={:?}, offsuited_details.len(): {}",
-            hand_rank,
-            offsuited_details.len()
-        );
+        let is_flush = cards.iter().all(|card| card.suit() == cards[0].suit());
+        let is_wheel = ranks == [12, 3, 2, 1, 0];
+        let is_straight = groups.len() == 5 && (ranks[0] - ranks[4] == 4 || is_wheel);
+        if is_wheel {
+            ranks = [3, 2, 1, 0, 12];
+        }
 
-        // 三条
-        {
-            for rrr_value in Rank::VALUE_2..=Rank::VALUE_A {
-                for r2_value in Rank::VALUE_2..=Rank::VALUE_A {
-                    if r2_value == rrr_value {
-                        continue;
-                    }
+        let category = match (is_straight, is_flush, groups[0].0, groups[1].0) {
+            (true, true, _, _) => HandCategory::StraightFlush,
+            (_, _, 4, _) => HandCategory::Quads,
+            (_, _, 3, 2) => HandCategory::FullHouse,
+            (_, true, _, _) => HandCategory::Flush,
+            (true, _, _, _) => HandCategory::Straight,
+            (_, _, 3, _) => HandCategory::Trips,
+            (_, _, 2, 2) => HandCategory::TwoPair,
+            (_, _, 2, _) => HandCategory::Pair,
+            _ => HandCategory::HighCard,
+        };
 
-                    for r1_value in Rank::VALUE_2..r2_value {
-                        if r1_value == rrr_value {
-                            continue;
-                        }
+        (category, ranks)
+    }
 
-                        let card_ranks = [rrr_value, rrr_value, rrr_value, r1_value, r2_value];
-                        offsuited_details.push((card_ranks, hand_rank.clone()));
-                        hand_rank.inc();
-                    }
+    fn reference_key(hand_rank: HandRank) -> (HandCategory, [i32; 5]) {
+        (
+            hand_rank.category(),
+            hand_rank.ranks().map(|rank| rank.value),
+        )
+    }
+
+    /// The hand rank of the best [`reference_get5`] among the 21 ways to drop two cards.
+    fn reference_get7(seven: [Card; 7]) -> HandRank {
+        static HAND_RANKS: OnceLock<HashMap<(HandCategory, [i32; 5]), HandRank>> = OnceLock::new();
+        let hand_ranks = HAND_RANKS.get_or_init(|| {
+            (0..HandRank::NUM.value())
+                .map(HandRank::from_value)
+                .map(|hand_rank| (reference_key(hand_rank), hand_rank))
+                .collect()
+        });
+
+        let mut best = None;
+        for skip0 in 0..7 {
+            for skip1 in skip0 + 1..7 {
+                let mut five = [seven[0]; 5];
+                for (pos, i) in (0..7).filter(|&i| i != skip0 && i != skip1).enumerate() {
+                    five[pos] = seven[i];
                 }
+                best = max(best, Some(reference_get5(five)));
             }
         }
+        hand_ranks[&best.unwrap()]
+    }
 
-        println!(
-            "finished process 三条, hand_rank={:?}, offsuited_details.len(): {}",
-            hand_rank,
-            offsuited_details.len()
-        );
+    fn cards<const N: usize>(abbr: &str) -> [Card; N] {
+        std::array::from_fn(|i| Card::parse(&abbr[2 * i..2 * i + 2]).unwrap())
+    }
 
-        self.process_no_pair(&mut hand_rank, &mut offsuited_details, false, true);
+    #[test]
+    fn test_hand_rank_ranks() {
+        let hand_ranker = hand_ranker();
 
-        println!(
-            "finished process 顺子, hand_rank={:?}, offsuited_details.len(): {}",
-            hand_rank,
-            offsuited_details.len()
-        );
+        for value in 0..HandRank::NUM.value() {
+            let hand_rank = HandRank::from_value(value);
+            let is_flush = matches!(
+                hand_rank.category(),
+                HandCategory::Flush | HandCategory::StraightFlush
+            );
 
-        self.process_no_pair(&mut hand_rank, &mut offsuited_details, true, false);
+            let mut i = 0;
+            let cards = hand_rank.ranks().map(|rank| {
+                let suit_value = if is_flush { 3 } else { i % 4 };
+                i += 1;
+                Card::from_rank_suit_value(rank.value, suit_value)
+            });
 
-        println!(
-            "finished process 同花, hand_rank={:?}, offsuited_details.len(): {}",
-            hand_rank,
-            offsuited_details.len()
+            assert_eq!(hand_ranker.get5(cards), hand_rank, "{:?}", cards);
+        }
+    }
+
+    #[test]
+    fn test_best_hand() {
+        let hand_ranker = hand_ranker();
+
+        let best_hand = hand_ranker.best_hand(cards("AsKd"), cards("KhKc7s7d2c"));
+        assert_eq!(best_hand.cards, cards("KdKhKc7s7d"));
+        assert_eq!(best_hand.hole_cards, cards::<1>("Kd"));
+        assert_eq!(
+            best_hand.hand_rank.description(),
+            "Full house, Kings full of Sevens"
         );
 
-        // 葫芦
-        {
-            for rrr_value in Rank::VALUE_2..=Rank::VALUE_A {
-                for rr_value in Rank::VALUE_2..=Rank::VALUE_A {
-                    if rr_value == rrr_value {
-                        continue;
-                    }
+        let best_hand = hand_ranker.best_hand(cards("AsAd"), cards("Th9h8h7h6h"));
+        assert_eq!(best_hand.cards, cards("Th9h8h7h6h"));
+        assert!(best_hand.hole_cards.is_empty());
 
-                    let card_ranks = [rrr_value, rrr_value, rrr_value, rr_value, rr_value];
-                    offsuited_details.push((card_ranks, hand_rank.clone()));
-                    hand_rank.inc();
-                }
-            }
+        let best_hand = hand_ranker.best_hand(cards("2c3c"), cards("9cTcJcAdKd"));
+        assert_eq!(best_hand.cards, cards("JcTc9c3c2c"));
+
+        let mut deck = (0..52).map(Card::from_value).collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(12);
+        for _ in 0..10_000 {
+            deck.shuffle(&mut rng);
+            let hole = [deck[0], deck[1]];
+            let board = [deck[2], deck[3], deck[4], deck[5], deck[6]];
+
+            let best_hand = hand_ranker.best_hand(hole, board);
+            let seven = [
+                hole[0], hole[1], board[0], board[1], board[2], board[3], board[4],
+            ];
+            assert_eq!(best_hand.hand_rank, hand_ranker.get7(seven), "{:?}", seven);
+            assert_eq!(hand_ranker.get5(best_hand.cards), best_hand.hand_rank);
         }
+    }
 
-        // 四条
-        {
-            for rrrr in Rank::VALUE_2..=Rank::VALUE_A {
-                for r in Rank::VALUE_2..=Rank::VALUE_A {
-                    if r == rrrr {
-                        continue;
+    #[test]
+    fn test_get6() {
+        let hand_ranker = hand_ranker();
+        let max_get5 = |six: [Card; 6]| {
+            (0..6)
+                .map(|skip| {
+                    let mut five = [six[0]; 5];
+                    for (pos, i) in (0..6).filter(|&i| i != skip).enumerate() {
+                        five[pos] = six[i];
                     }
+                    hand_ranker.get5(five)
+                })
+                .max()
+                .unwrap()
+        };
 
-                    let card_ranks = [rrrr, rrrr, rrrr, rrrr, r];
-                    offsuited_details.push((card_ranks, hand_rank.clone()));
-                    hand_rank.inc();
-                }
-            }
+        for abbr in [
+            "AsKsQsJsTs9d",
+            "Ac2d3h4s5c9d",
+            "2c3c4c5c7c8c",
+            "AhAdAcKsKhKd",
+            "9s9h9d9c2s2h",
+            "7c8d9hTsJc3d",
+        ] {
+            let six = cards(abbr);
+            assert_eq!(hand_ranker.get6(six), max_get5(six), "{}", abbr);
         }
 
-        self.process_no_pair(&mut hand_rank, &mut offsuited_details, true, true);
-
-        if hand_rank != HandRank::NUM {
-            panic!("hand_rank != NUM");
+        let mut deck = (0..52).map(Card::from_value).collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(13);
+        for _ in 0..20_000 {
+            deck.shuffle(&mut rng);
+            let six = [deck[0], deck[1], deck[2], deck[3], deck[4], deck[5]];
+            assert_eq!(hand_ranker.get6(six), max_get5(six), "{:?}", six);
         }
+    }
 
-        for s in (0 as usize)..((1 << 13) as usize) {
-            for i in 0..13 {
-                if test_bit(s, i) {
-                    let ns = flip_bit(s, i);
-                    self.suited[s] = max(self.suited[s].clone(), self.suited[ns].clone());
-                }
-            }
+    #[test]
+    fn test_evaluate() {
+        let hand_ranker = hand_ranker();
+        let mask = |cards: &[Card]| cards.iter().fold(0, |mask, card| mask | card.mask());
+
+        let mut deck = (0..52).map(Card::from_value).collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(19);
+        for _ in 0..20_000 {
+            deck.shuffle(&mut rng);
+            let seven = [
+                deck[0], deck[1], deck[2], deck[3], deck[4], deck[5], deck[6],
+            ];
+            let six = [deck[0], deck[1], deck[2], deck[3], deck[4], deck[5]];
+            let five = [deck[0], deck[1], deck[2], deck[3], deck[4]];
+
+            assert_eq!(hand_ranker.evaluate(mask(&seven)), hand_ranker.get7(seven));
+            assert_eq!(hand_ranker.evaluate(mask(&six)), hand_ranker.get6(six));
+            assert_eq!(hand_ranker.evaluate(mask(&five)), hand_ranker.get5(five));
+
+            // hole cards first, then the board card by card
+            let hole = PartialHand::from_cards(&seven[..2]);
+            let board = PartialHand::from_cards(&seven[2..]);
+            assert_eq!(
+                hand_ranker.evaluate_partial(&hole.union(board)),
+                hand_ranker.get7(seven)
+            );
+            assert_eq!(
+                hole.union(PartialHand::from_cards(&seven[2..6]))
+                    .with_card(seven[6]),
+                PartialHand::from_mask(mask(&seven))
+            );
         }
 
-        println!("finished process suited");
+        let flush = cards("2c3c4c5c7c8dAd");
+        assert_eq!(hand_ranker.evaluate(mask(&flush)), hand_ranker.get7(flush));
+    }
 
-        for (mut r01234_values, hand_rank) in offsuited_details {
-            println!("r01234={:?}", r01234_values);
+    #[test]
+    #[should_panic(expected = "4 cards")]
+    fn test_evaluate_too_few_cards() {
+        let four: [Card; 4] = cards("AsKsQsJs");
+        hand_ranker().evaluate(four.iter().fold(0, |mask, card| mask | card.mask()));
+    }
 
-            {
-                r01234_values.sort();
-                loop {
-                    let mut hash5 = 0;
-                    for r_value in r01234_values {
-                        hash5 = hash5 * 13 + r_value;
-                    }
-                    self.offsuited5[hash5 as usize] = hand_rank;
+    #[test]
+    #[should_panic(expected = "8 cards")]
+    fn test_evaluate_partial_too_many_cards() {
+        hand_ranker().evaluate_partial(&PartialHand::from_cards(&cards::<8>("AsKsQsJsTs9s8s7s")));
+    }
+
+    #[test]
+    #[should_panic(expected = "not a card mask")]
+    fn test_evaluate_beyond_deck() {
+        let six: [Card; 6] = cards("AsKsQsJsTs9s");
+        hand_ranker().evaluate(six.iter().fold(1 << 52, |mask, card| mask | card.mask()));
+    }
 
-                    if !r01234_values.next_permutation() {
-                        break;
+    #[test]
+    fn test_get_omaha() {
+        let hand_ranker = hand_ranker();
+        let get_omaha = |hole: &str, board: &str| {
+            let hole = OmahaHand::parse(hole).unwrap();
+            hand_ranker.get_omaha(hole.cards(), cards(board))
+        };
+
+        // hold'em would make a straight flush with As and the board
+        assert_eq!(
+            get_omaha("AsAhKdKc", "2s3s4s5s9d"),
+            hand_ranker.get5(cards("AsAh9d5s4s"))
+        );
+        assert_eq!(
+            get_omaha("AsKsQhJh", "Ts9s2s7d3c"),
+            hand_ranker.get5(cards("AsKsTs9s2s"))
+        );
+        // the board straight does not play
+        assert_eq!(
+            get_omaha("AcAdKhKs", "5s6d7h8c9s"),
+            hand_ranker.get5(cards("AcAd9s8c7h"))
+        );
+        assert_eq!(
+            get_omaha("AsKsQhJh2d", "3c4c5dKdTh"),
+            hand_ranker.get5(cards("As2d3c4c5d"))
+        );
+
+        let mut deck = (0..52).map(Card::from_value).collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(21);
+        for num_hole_cards in [4, 5] {
+            for _ in 0..2_000 {
+                deck.shuffle(&mut rng);
+                let hole = &deck[..num_hole_cards];
+                let board = [deck[5], deck[6], deck[7], deck[8], deck[9]];
+
+                // the best five of hole and board that use exactly two hole cards
+                let all = [hole, &board[..]].concat();
+                let mut expected = HandRank::ERROR;
+                for subset in 0_u32..1 << all.len() {
+                    let num_hole = (subset & ((1 << num_hole_cards) - 1)).count_ones();
+                    if subset.count_ones() != 5 || num_hole != 2 {
+                        continue;
                     }
+                    let five = (0..all.len())
+                        .filter(|i| subset & (1 << i) != 0)
+                        .map(|i| all[i])
+                        .collect::<Vec<_>>();
+                    expected = max(expected, hand_ranker.get5(five.try_into().unwrap()));
                 }
+
+                assert_eq!(hand_ranker.get_omaha(hole, board), expected, "{:?}", all);
             }
+        }
+    }
 
-            for r6_value in Rank::VALUE_2..=Rank::VALUE_A {
-                for r5_value in Rank::VALUE_2..=r6_value {
-                    let mut r0123456_values = [
-                        r01234_values[0],
-                        r01234_values[1],
-                        r01234_values[2],
-                        r01234_values[3],
-                        r01234_values[4],
-                        r5_value,
-                        r6_value,
-                    ];
-                    r0123456_values.sort();
+    #[test]
+    fn test_evaluate_batch() {
+        let hand_ranker = hand_ranker();
+
+        let mut deck = (0..52).map(Card::from_value).collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(20);
+        let hands = (0..1000)
+            .map(|_| {
+                deck.shuffle(&mut rng);
+                PartialHand::from_cards(&deck[..7])
+            })
+            .collect::<Vec<_>>();
+
+        let mut out = vec![HandRank::ERROR; hands.len()];
+        hand_ranker.evaluate_batch(&hands, &mut out);
+        for (hand, hand_rank) in hands.iter().zip(&out) {
+            assert_eq!(*hand_rank, hand_ranker.evaluate_partial(hand));
+        }
 
-                    loop {
-                        let mut hash7 = 0;
-                        for r_value in r0123456_values {
-                            hash7 = hash7 * 13 + r_value;
-                        }
-                        self.offsuited7[hash7 as usize] =
-                            max(self.offsuited7[hash7 as usize], hand_rank);
+        let board = PartialHand::from_cards(&cards::<5>("AsKsQh7c2d"));
+        let holes = (0..52)
+            .flat_map(|c1| (0..c1).map(move |c2| [Card::from_value(c1), Card::from_value(c2)]))
+            .map(|hole| PartialHand::from_cards(&hole))
+            .collect::<Vec<_>>();
+        let mut out = vec![HandRank::ERROR; holes.len()];
+        hand_ranker.evaluate_board_batch(&board, &holes, &mut out);
+        for (hole, hand_rank) in holes.iter().zip(&out) {
+            if hole.mask() & board.mask() != 0 {
+                assert_eq!(*hand_rank, HandRank::ERROR);
+            } else {
+                assert_eq!(*hand_rank, hand_ranker.evaluate_partial(&hole.union(board)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_hand_rank_order() {
+        // hand ranks must grow with the reference ordering
+        for value in 1..HandRank::NUM.value() {
+            assert!(
+                reference_key(HandRank::from_value(value - 1))
+                    < reference_key(HandRank::from_value(value)),
+                "{}",
+                value
+            );
+        }
+    }
 
-                        if !r0123456_values.next_permutation() {
-                            break;
+    #[test]
+    fn test_get5_exhaustive() {
+        let hand_ranker = hand_ranker();
+        let mut num_hands = 0;
+
+        for c0 in 0..52 {
+            for c1 in c0 + 1..52 {
+                for c2 in c1 + 1..52 {
+                    for c3 in c2 + 1..52 {
+                        for c4 in c3 + 1..52 {
+                            let five = [c0, c1, c2, c3, c4].map(Card::from_value);
+                            let hand_rank = hand_ranker.get5(five);
+                            assert!(
+                                hand_rank.is_valid()
+                                    && reference_key(hand_rank) == reference_get5(five),
+                                "{:?} {:?}",
+                                five,
+                                hand_rank
+                            );
+                            num_hands += 1;
                         }
                     }
                 }
             }
         }
 
-        println!("calc_data_done");
+        assert_eq!(num_hands, 2_598_960);
     }
 
-    fn save_data(&self, data_dir: &str) -> Result<(), ()> {
-        if data_dir.is_empty() {
-            println!("empty data_dir");
-            return Err(());
+    #[test]
+    fn test_get5_flush_per_suit() {
+        let hand_ranker = hand_ranker();
+        let suits = [Suit::CLUB, Suit::DIAMOND, Suit::HEART, Suit::SPADE];
+        let hand = |ranks: [i32; 5], suits: [&Suit; 5]| {
+            std::array::from_fn(|i| {
+                Card::from_rank_suit_value(ranks[i], suits[i].as_usize() as i32)
+            })
+        };
+        let flush_ranks = [12, 10, 7, 3, 0];
+        let straight_flush_ranks = [8, 7, 6, 5, 4];
+
+        for suit in &suits {
+            let flush = hand_ranker.get5(hand(flush_ranks, [suit; 5]));
+            assert_eq!(
+                flush.category(),
+                HandCategory::Flush,
+                "suit {}",
+                suit.as_usize()
+            );
+            let straight_flush = hand_ranker.get5(hand(straight_flush_ranks, [suit; 5]));
+            assert_eq!(
+                straight_flush.category(),
+                HandCategory::StraightFlush,
+                "suit {}",
+                suit.as_usize()
+            );
+
+            // four of the suit and one off-suit card, wherever it sits
+            for other in suits.iter().filter(|&other| other != suit) {
+                for pos in 0..5 {
+                    let mut hand_suits = [suit; 5];
+                    hand_suits[pos] = other;
+                    let cards = hand(flush_ranks, hand_suits);
+                    assert_eq!(
+                        hand_ranker.get5(cards).category(),
+                        HandCategory::HighCard,
+                        "{:?}",
+                        cards
+                    );
+                    assert_eq!(flush_suit(&cards), None);
+                }
+            }
+            assert_eq!(
+                flush_suit(&hand(flush_ranks, [suit; 5])),
+                Some(suit.as_usize())
+            );
         }
 
-        let mut offsuited7_sum: i64 = 0;
-        let mut suited_sum: i64 = 0;
+        // suits whose values AND to non-zero or to zero without being a flush
+        for hand_suits in [
+            [
+                &Suit::DIAMOND,
+                &Suit::HEART,
+                &Suit::SPADE,
+                &Suit::SPADE,
+                &Suit::SPADE,
+            ],
+            [
+                &Suit::HEART,
+                &Suit::SPADE,
+                &Suit::HEART,
+                &Suit::SPADE,
+                &Suit::HEART,
+            ],
+            [
+                &Suit::DIAMOND,
+                &Suit::SPADE,
+                &Suit::DIAMOND,
+                &Suit::SPADE,
+                &Suit::SPADE,
+            ],
+            [
+                &Suit::CLUB,
+                &Suit::DIAMOND,
+                &Suit::HEART,
+                &Suit::SPADE,
+                &Suit::CLUB,
+            ],
+        ] {
+            let cards = hand(straight_flush_ranks, hand_suits);
+            assert_eq!(hand_ranker.get5(cards).category(), HandCategory::Straight);
+        }
+    }
 
-        // offsuited5
-        let mut offsuited5_sum: i64 = 0;
-        {
-            let filename = format!("{}/offsuited5.bin", data_dir);
-            let mut fout = File::create(filename).unwrap();
+    #[test]
+    fn test_short_deck() {
+        let hand_ranker = HandRanker::generate_short_deck();
+        let get5 = |abbr: &str| hand_ranker.get5(cards(abbr));
+
+        assert_eq!(hand_ranker.deck().len(), 36);
+        assert!(get5("AsKsQs9s7s") > get5("AhAdAcKdKh"));
+        assert!(get5("As6d7h8c9s") > get5("9s9h9d7c6h"));
+        assert!(get5("As6d7h8c9s") < get5("6s7d8h9cTs"));
+        assert!(get5("As6s7s8s9s") > get5("AhAdAcAsKh"));
+
+        // every short-deck hand against a reference ordering
+        let short_deck_key = |five: [Card; 5]| {
+            let (mut category, mut ranks) = reference_get5(five);
+            if ranks == [12, 7, 6, 5, 4] {
+                let is_flush = category == HandCategory::Flush;
+                category = if is_flush {
+                    HandCategory::StraightFlush
+                } else {
+                    HandCategory::Straight
+                };
+                ranks = [7, 6, 5, 4, 12];
+            }
+            let order = match category {
+                HandCategory::Flush => HandCategory::FullHouse as u8,
+                HandCategory::FullHouse => HandCategory::Flush as u8,
+                category => category as u8,
+            };
+            (order, ranks)
+        };
 
-            for (i, v) in self.offsuited5.iter().enumerate() {
-                let v = v.value() as i16;
-                fout.write_all(v.to_le_bytes().as_slice()).unwrap();
-                offsuited5_sum += (v as i64) * (i as i64);
+        let deck = hand_ranker.deck();
+        let mut keyed = Vec::new();
+        for c0 in 0..36 {
+            for c1 in c0 + 1..36 {
+                for c2 in c1 + 1..36 {
+                    for c3 in c2 + 1..36 {
+                        for c4 in c3 + 1..36 {
+                            let five = [c0, c1, c2, c3, c4].map(|i| deck[i]);
+                            keyed.push((short_deck_key(five), hand_ranker.get5(five)));
+                        }
+                    }
+                }
             }
         }
+        keyed.sort();
+        for pair in keyed.windows(2) {
+            let ((key0, hand_rank0), (key1, hand_rank1)) = (pair[0], pair[1]);
+            assert_eq!(key0 == key1, hand_rank0 == hand_rank1, "{:?}", pair);
+            assert!(hand_rank0 <= hand_rank1, "{:?}", pair);
+        }
+        assert_eq!(keyed[0].1, HandRank::from_value(0));
+        assert_eq!(
+            keyed.last().unwrap().1,
+            HandRank::from_value(table_gen::NUM_SHORT_DECK_HAND_RANKS as i32 - 1)
+        );
 
-        // offsuited7
-        {
-            let filename = format!("{}/offsuited7.bin", data_dir);
-            let mut fout = File::create(filename).unwrap();
+        let best_hand = hand_ranker.best_hand(cards("As6d"), cards("7h8c9sKdKh"));
+        assert_eq!(best_hand.cards, cards("9s8c7h6dAs"));
+        assert!(hand_ranker.save_to("unused").is_err());
+    }
 
-            for (i, v) in self.offsuited7.iter().enumerate() {
-                let v = v.value() as i16;
-                fout.write_all(v.to_le_bytes().as_slice()).unwrap();
-                offsuited7_sum += (v as i64) * (i as i64);
-            }
+    #[test]
+    fn test_get7_sample() {
+        let hand_ranker = hand_ranker();
+
+        let edge_cases = [
+            // wheel, and a six-high straight beating it
+            ("Ac2d3h4s5c", "Kd9h"),
+            ("Ac2d3h4s5c", "6d9h"),
+            // steel wheel over a higher flush
+            ("As2s3s4s5s", "KsQs"),
+            // flush over straight
+            ("9h8h7d6h5c", "2h3h"),
+            // straight flush over quads
+            ("9h8h7h6h5h", "5d5s"),
+            // three pairs play the best two with the best kicker
+            ("AsAdKcKd7h", "7s2c"),
+            // two sets make a full house
+            ("QsQdQc9d9h", "9s2c"),
+            // quads with a set on board
+            ("8s8d8c8h3h", "3s3c"),
+            // ace-high straight and royal flush
+            ("AsKdQcJhTs", "2c3d"),
+            ("AsKsQsJsTs", "9s8s"),
+            // six cards of one suit
+            ("2c4c6c8cTc", "Qc3d"),
+        ];
+        for (board, hole) in edge_cases {
+            let seven: [Card; 7] = cards(&format!("{}{}", board, hole));
+            assert_eq!(
+                hand_ranker.get7(seven),
+                reference_get7(seven),
+                "{}{}",
+                board,
+                hole
+            );
         }
 
-        // suited
-        {
-            let filename = format!("{}/suited.bin", data_dir);
-            let mut fout = File::create(filename).unwrap();
+        assert_eq!(
+            hand_ranker.get7(cards("Ac2d3h4s5cKd9h")).description(),
+            "Straight, Five high"
+        );
+        assert_eq!(
+            hand_ranker.get7(cards("As2s3s4s5sKsQs")).description(),
+            "Straight flush, Five high"
+        );
+        assert_eq!(
+            hand_ranker.get7(cards("9h8h7d6h5c2h3h")).category(),
+            HandCategory::Flush
+        );
 
-            for (i, v) in self.suited.iter().enumerate() {
-                let v = v.value() as i16;
-                fout.write_all(v.to_le_bytes().as_slice()).unwrap();
-                suited_sum += (v as i64) * (i as i64);
-            }
+        let mut deck = (0..52).map(Card::from_value).collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(14);
+        for _ in 0..100_000 {
+            deck.shuffle(&mut rng);
+            let seven = [
+                deck[0], deck[1], deck[2], deck[3], deck[4], deck[5], deck[6],
+            ];
+            assert_eq!(
+                hand_ranker.get7(seven),
+                reference_get7(seven),
+                "{:?}",
+                seven
+            );
         }
+    }
 
-        println!(
-            "offsuited5_sum={} offsuited7_sum={} suited_sum={}",
-            offsuited5_sum, offsuited7_sum, suited_sum
-        );
+    #[test]
+    fn test_save_and_load() {
+        let hand_ranker = hand_ranker();
+        let temp_path = TempPath::new("hand_ranker_save_and_load");
+        let data_dir = temp_path.as_str();
 
-        Ok(())
+        hand_ranker.save_to(data_dir).unwrap();
+        let loaded = HandRanker::load_from(data_dir).unwrap();
+
+        let seven = cards("AsKsQsJsTs9s8s");
+        assert_eq!(loaded.get7(seven), hand_ranker.get7(seven));
+
+        std::fs::write(format!("{}/suited.bin", data_dir), [0; 4]).unwrap();
+        assert!(HandRanker::load_from(data_dir).is_err());
+
+        std::fs::remove_dir_all(data_dir).unwrap();
+        assert!(HandRanker::load_from(data_dir).is_err());
     }
 
-    fn load_data(&mut self, data_dir: &str) -> Result<(), ()> {
-        if data_dir.is_empty() {
-            println!("empty data_dir");
-            return Err(());
-        }
+    #[cfg(feature = "embedded")]
+    #[test]
+    fn test_embedded() {
+        let hand_ranker = hand_ranker();
+        let embedded = HandRanker::embedded();
 
-        // offsuited5
-        {
-            let mut offsuited5_sum: i64 = 0;
-
-            let filename = format!("{}/offsuited5.bin", data_dir);
-            let fin = File::open(filename).map_err(|_| ())?;
-            let mut reader: BufReader<File> = BufReader::new(fin);
-
-            for i in 0..self.offsuited5.len() {
-                let mut v = [0; 2];
-                reader.read_exact(&mut v).unwrap();
-                let v = i16::from_le_bytes(v);
-                self.offsuited5[i] = HandRank::from_value(v as i32);
-                offsuited5_sum += (v as i64) * (i as i64);
-            }
+        assert!(embedded.suited == hand_ranker.suited);
+        assert!(embedded.offsuited5 == hand_ranker.offsuited5);
+        assert!(embedded.offsuited6 == hand_ranker.offsuited6);
+        assert!(embedded.offsuited7 == hand_ranker.offsuited7);
+    }
 
-            if offsuited5_sum != 156362476943832 {
-                println!("checksum_failed offsuited5_sum={}", offsuited5_sum);
-                return Err(());
-            }
-        }
+    #[test]
+    fn test_table_file_format() {
+        let hand_ranker = hand_ranker();
+        let bytes = encode_table(&hand_ranker.suited);
+        let mut table = vec![HandRank::ERROR; 1 << 13];
+        let checksum = HandRanker::SUITED_CHECKSUM;
+
+        decode_table(&bytes, &mut table, checksum).unwrap();
+        assert_eq!(table, hand_ranker.suited);
+
+        let error = |bytes: &[u8]| {
+            format!(
+                "{:?}",
+                decode_table(bytes, &mut table.clone(), checksum).unwrap_err()
+            )
+        };
 
-        // offsuited7
-        {
-            let mut offsuited7_sum: i64 = 0;
-
-            let filename = format!("{}/offsuited7.bin", data_dir);
-            let fin = File::open(filename).map_err(|_| ())?;
-            let mut reader: BufReader<File> = BufReader::new(fin);
-
-            for i in 0..self.offsuited7.len() {
-                let mut v = [0; 2];
-                reader.read_exact(&mut v).unwrap();
-                let v = i16::from_le_bytes(v);
-                self.offsuited7[i] = HandRank::from_value(v as i32);
-                offsuited7_sum += (v as i64) * (i as i64);
+        // the headerless format of earlier versions
+        assert!(error(&bytes[TABLE_HEADER_LEN..]).contains("not a hand ranker table"));
+        assert!(error(&bytes[..bytes.len() - 1]).contains("payload has"));
+
+        let mut patched = bytes.clone();
+        patched[4] = 2;
+        assert!(error(&patched).contains("unsupported table version"));
+
+        let mut patched = bytes.clone();
+        patched[8..12].copy_from_slice(&TABLE_BYTE_ORDER_MARK.to_be_bytes());
+        assert!(error(&patched).contains("byte order mark"));
+
+        let mut patched = bytes.clone();
+        patched[16] ^= 1;
+        assert!(error(&patched).contains("entries"));
+
+        let mut patched = bytes.clone();
+        patched[TABLE_HEADER_LEN + 100] ^= 1;
+        assert!(error(&patched).contains("checksum mismatch"));
+
+        assert!(format!(
+            "{:?}",
+            decode_table(&bytes, &mut table, checksum + 1).unwrap_err()
+        )
+        .contains("differs"));
+    }
+
+    /// The 13^7 offsuited7 table the compact one replaced, built the way calc_data used to:
+    /// every non-flush five-card hand plus any two more ranks, stored under every ordering of
+    /// the seven ranks. The hands are grouped by their sorted seven ranks first, so that every
+    /// ordering is written once with the best of them rather than once per hand.
+    fn legacy_offsuited7() -> Vec<HandRank> {
+        use permutohedron::LexicalPermutation;
+
+        let mut best_by_ranks = std::collections::HashMap::new();
+        for value in 0..HandRank::NUM.value() {
+            let hand_rank = HandRank::from_value(value);
+            if matches!(
+                hand_rank.category(),
+                HandCategory::Flush | HandCategory::StraightFlush
+            ) {
+                continue;
             }
 
-            if offsuited7_sum != 7479833936848761882 {
-                println!("checksum_failed offsuited7_sum={}", offsuited7_sum);
-                return Err(());
+            let r01234_values = hand_rank.ranks().map(|r| r.as_usize());
+            for r6_value in 0..13 {
+                for r5_value in 0..=r6_value {
+                    let mut r0123456_values = [
+                        r01234_values[0],
+                        r01234_values[1],
+                        r01234_values[2],
+                        r01234_values[3],
+                        r01234_values[4],
+                        r5_value,
+                        r6_value,
+                    ];
+                    r0123456_values.sort();
+
+                    let best = best_by_ranks
+                        .entry(r0123456_values)
+                        .or_insert(HandRank::ERROR);
+                    *best = max(*best, hand_rank);
+                }
             }
         }
 
-        // suited
-        {
-            let mut suited_sum: i64 = 0;
-
-            let filename = format!("{}/suited.bin", data_dir);
-            let fin = File::open(filename).map_err(|_| ())?;
-            let mut reader: BufReader<File> = BufReader::new(fin);
-
-            for i in 0..self.suited.len() {
-                let mut v = [0; 2];
-                reader.read_exact(&mut v).unwrap();
-                let v = i16::from_le_bytes(v); // 注意这里不能用 u16, 否则会生成 65535
-                self.suited[i] = HandRank::from_value(v as i32);
-                suited_sum += (v as i64) * (i as i64);
-            }
+        let mut offsuited7 = vec![HandRank::ERROR; 13usize.pow(7)];
+        for (mut r0123456_values, hand_rank) in best_by_ranks {
+            loop {
+                let mut hash7 = 0;
+                for r_value in r0123456_values {
+                    hash7 = hash7 * 13 + r_value;
+                }
+                offsuited7[hash7] = max(offsuited7[hash7], hand_rank);
 
-            if suited_sum != 217636760248 {
-                println!("checksum_failed suited_sum={}", suited_sum);
-                return Err(());
+                if !r0123456_values.next_permutation() {
+                    break;
+                }
             }
         }
-
-        Ok(())
+        offsuited7
     }
-}
 
-fn test_bit(s: usize, i: u8) -> bool {
-    (s & (1 << i)) != 0
-}
-
-fn flip_bit(s: usize, i: u8) -> usize {
-    s ^ (1 << i)
+    #[test]
+    fn test_offsuited7_against_legacy_table() {
+        let hand_ranker = hand_ranker();
+        let legacy = legacy_offsuited7();
+
+        for (hash7, &hand_rank) in legacy.iter().enumerate() {
+            let mut ranks = [Rank::from_value(0); 7];
+            let mut rest = hash7;
+            for rank in ranks.iter_mut().rev() {
+                *rank = Rank::from_value((rest % 13) as i32);
+                rest /= 13;
+            }
+            assert_eq!(hand_ranker.get7_offsuited(ranks), hand_rank, "{}", hash7);
+        }
+    }
 }
@@ -1,11 +1,41 @@
+mod canonicalizer;
 mod equitizer;
+mod equity_cache;
 mod hand_ranker;
+mod low_ranker;
+mod preflop_table;
+mod table_gen;
+#[cfg(test)]
+mod test_util;
 mod types;
 
+pub use canonicalizer::canonicalize;
+pub use canonicalizer::CanonicalCards;
+pub use canonicalizer::SuitPermutation;
 pub use equitizer::Equitizer;
+pub use equity_cache::AppendFileCache;
+pub use equity_cache::EquityCache;
+pub use equity_cache::EquityKey;
+pub use equity_cache::MemoryCache;
+pub use equity_cache::ReadOnlyFileCache;
 pub use hand_ranker::HandRanker;
+pub use low_ranker::LowRanker;
+pub use preflop_table::PreflopTable;
+pub use types::BestHand;
 pub use types::Card;
+pub use types::Combo;
+pub use types::ComboEquity;
+pub use types::EquityResult;
+pub use types::HandCategory;
 pub use types::HandRank;
+pub use types::HiLoEquityResult;
+pub use types::LowRank;
 pub use types::MixedRange;
+pub use types::MonteCarloConfig;
+pub use types::MonteCarloResult;
+pub use types::OmahaHand;
+pub use types::OmahaRange;
+pub use types::PartialHand;
 pub use types::PureRange;
 pub use types::Range;
+pub use types::Rank;
@@ -0,0 +1,348 @@
+use super::types::Card;
+use super::types::LowRank;
+use crate::hand_ranker::{best_of_7, rank_multiset_index, POW_13_5};
+use crate::table_gen;
+use crate::table_gen::Ranking;
+use std::cmp::max;
+use std::collections::HashMap;
+
+/// Lowball evaluator, the counterpart of [`crate::HandRanker`] for the low half of split pots
+/// and for deuce-to-seven. Its rank-only tables are indexed like `offsuited5` and `offsuited7`.
+///
+/// - Ace-to-five: aces are low, straights and flushes do not count, 5-4-3-2-A is the best
+///   hand. Eight-or-better is the same, but only for five different ranks of 8 or lower.
+/// - Deuce-to-seven: aces are high and straights and flushes count, so the best hand is
+///   7-5-4-3-2 offsuit and A-2-3-4-5 is an ace high, not a straight.
+pub struct LowRanker {
+    ace_to_five5: Vec<LowRank>,
+    ace_to_five7: Vec<LowRank>,
+    deuce_to_seven_suited: Vec<LowRank>,
+    deuce_to_seven5: Vec<LowRank>,
+}
+
+impl LowRanker {
+    /// Builds every table in memory, without any IO.
+    pub fn generate() -> Self {
+        let ace_to_five5 = calc_ace_to_five5();
+        let ace_to_five7 = best_of_7(&ace_to_five5, LowRank::NO_LOW);
+
+        // the worst high hand is the best low
+        let tables = table_gen::generate(Ranking::NoWheel);
+        let invert = |table: Vec<i16>| {
+            table
+                .into_iter()
+                .map(|v| match v {
+                    table_gen::ERROR => LowRank::NO_LOW,
+                    v => LowRank::from_value(LowRank::NUM_DEUCE_TO_SEVEN.value() - 1 - v as i32),
+                })
+                .collect()
+        };
+
+        Self {
+            ace_to_five5,
+            ace_to_five7,
+            deuce_to_seven_suited: invert(tables.suited),
+            deuce_to_seven5: invert(tables.offsuited5),
+        }
+    }
+
+    pub fn ace_to_five5(&self, cards: [Card; 5]) -> LowRank {
+        let mut hash = 0;
+        for card in cards {
+            hash = hash * 13 + card.rank().as_usize();
+        }
+        self.ace_to_five5[hash]
+    }
+
+    pub fn ace_to_five7(&self, cards: [Card; 7]) -> LowRank {
+        self.ace_to_five7[rank_multiset_index(cards.map(|card| card.rank().as_usize()))]
+    }
+
+    /// [`LowRanker::ace_to_five5`], or [`LowRank::NO_LOW`] for a hand worse than 8-7-6-5-4.
+    pub fn eight_or_better5(&self, cards: [Card; 5]) -> LowRank {
+        eight_or_better(self.ace_to_five5(cards))
+    }
+
+    pub fn eight_or_better7(&self, cards: [Card; 7]) -> LowRank {
+        eight_or_better(self.ace_to_five7(cards))
+    }
+
+    /// Omaha-8 low: the best [`LowRanker::eight_or_better5`] of exactly two of the `hole`
+    /// cards and exactly three of the `board` cards, like [`crate::HandRanker::get_omaha`].
+    pub fn omaha_eight_or_better(&self, hole: &[Card], board: [Card; 5]) -> LowRank {
+        let mut best = LowRank::NO_LOW;
+        for b0 in 0..5 {
+            for b1 in b0 + 1..5 {
+                for b2 in b1 + 1..5 {
+                    for h0 in 0..hole.len() {
+                        for h1 in h0 + 1..hole.len() {
+                            best = max(
+                                best,
+                                self.ace_to_five5([
+                                    hole[h0], hole[h1], board[b0], board[b1], board[b2],
+                                ]),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        eight_or_better(best)
+    }
+
+    pub fn deuce_to_seven5(&self, cards: [Card; 5]) -> LowRank {
+        let is_flush = cards.iter().all(|card| card.suit() == cards[0].suit());
+
+        if is_flush {
+            let mut suited_hash = 0;
+            for card in &cards {
+                suited_hash |= 1 << card.rank().as_usize();
+            }
+            self.deuce_to_seven_suited[suited_hash]
+        } else {
+            let mut hash = 0;
+            for card in cards {
+                hash = hash * 13 + card.rank().as_usize();
+            }
+            self.deuce_to_seven5[hash]
+        }
+    }
+
+    /// The best [`LowRanker::deuce_to_seven5`] of the 21 ways to drop two cards. Unlike
+    /// ace-to-five, the suits matter, so there is no seven-card table.
+    pub fn deuce_to_seven7(&self, cards: [Card; 7]) -> LowRank {
+        let mut best = LowRank::NO_LOW;
+        for skip0 in 0..7 {
+            for skip1 in skip0 + 1..7 {
+                let mut five = [cards[0]; 5];
+                for (pos, i) in (0..7).filter(|&i| i != skip0 && i != skip1).enumerate() {
+                    five[pos] = cards[i];
+                }
+                best = max(best, self.deuce_to_seven5(five));
+            }
+        }
+        best
+    }
+}
+
+fn eight_or_better(low_rank: LowRank) -> LowRank {
+    if low_rank >= LowRank::MIN_EIGHT_OR_BETTER {
+        low_rank
+    } else {
+        LowRank::NO_LOW
+    }
+}
+
+/// Ace-to-five low of every five ranks, base-13 indexed by the standard rank values. Fewer
+/// and smaller pairs beat more and bigger ones, then the highest card decides, then the next.
+fn calc_ace_to_five5() -> Vec<LowRank> {
+    // the sorted standard rank values of every possible hand, with its badness key
+    let mut hands = Vec::new();
+    let mut rank_values = [0; 5];
+    loop {
+        let mut counts = [0; 13];
+        for &r in &rank_values {
+            // ace low: A, 2, .., K become 0, 1, .., 12
+            counts[(r + 1) % 13] += 1;
+        }
+
+        if counts.iter().all(|&count| count <= 4) {
+            // (count, low value) groups, biggest group first, then highest card first
+            let mut groups = (0..13)
+                .filter(|&v| counts[v] > 0)
+                .map(|v| (counts[v], v))
+                .collect::<Vec<_>>();
+            groups.sort_by(|a, b| b.cmp(a));
+
+            let category = match (groups[0].0, groups.get(1).map_or(0, |g| g.0)) {
+                (1, _) => 0,
+                (2, 1) => 1,
+                (2, 2) => 2,
+                (3, 1) => 3,
+                (3, 2) => 4,
+                _ => 5,
+            };
+            let values = groups
+                .iter()
+                .flat_map(|&(count, v)| std::iter::repeat_n(v, count))
+                .collect::<Vec<_>>();
+            hands.push(((category, values), rank_values));
+        }
+
+        // next non-decreasing sequence
+        let Some(i) = (0..5).rev().find(|&i| rank_values[i] < 12) else {
+            break;
+        };
+        let r = rank_values[i] + 1;
+        rank_values[i..].fill(r);
+    }
+    assert_eq!(hands.len(), LowRank::NUM_ACE_TO_FIVE.value() as usize);
+
+    // worst first, so the best low gets the highest value
+    hands.sort_by(|a, b| b.0.cmp(&a.0));
+    let sorted_ranks = hands
+        .iter()
+        .enumerate()
+        .map(|(value, (_, rank_values))| (*rank_values, LowRank::from_value(value as i32)))
+        .collect::<HashMap<_, _>>();
+
+    let mut table = vec![LowRank::NO_LOW; POW_13_5];
+    for (hash5, low_rank) in table.iter_mut().enumerate() {
+        let mut ranks = [0; 5];
+        let mut rest = hash5;
+        for r in ranks.iter_mut().rev() {
+            *r = rest % 13;
+            rest /= 13;
+        }
+        ranks.sort();
+
+        if let Some(&value) = sorted_ranks.get(&ranks) {
+            *low_rank = value;
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OmahaHand;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+    use std::sync::OnceLock;
+
+    fn low_ranker() -> &'static LowRanker {
+        static LOW_RANKER: OnceLock<LowRanker> = OnceLock::new();
+        LOW_RANKER.get_or_init(LowRanker::generate)
+    }
+
+    fn cards<const N: usize>(abbr: &str) -> [Card; N] {
+        std::array::from_fn(|i| Card::parse(&abbr[2 * i..2 * i + 2]).unwrap())
+    }
+
+    #[test]
+    fn test_ace_to_five() {
+        let low = |abbr| low_ranker().ace_to_five5(cards(abbr));
+        let best = LowRank::from_value(LowRank::NUM_ACE_TO_FIVE.value() - 1);
+
+        assert_eq!(low("5h4d3c2sAh"), best);
+        // straights and flushes do not count
+        assert_eq!(low("5s4s3s2sAs"), best);
+        assert_eq!(low("KsKdKhKcQs"), LowRank::from_value(0));
+
+        let ordered = [
+            "QsJdTc9h8h",
+            "Ks2d3c4h5h",
+            "Ks9d7c5h3h",
+            "KsQdJcTh8h",
+            "AsAd2c3h4h",
+            "8s8d7c6h5h",
+            "2s2d3c3h4h",
+            "Ks9d9c8h8h",
+            "AsAdAc2h3h",
+            "2s2d2c3h3h",
+            "KsKdKcKhQs",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(low(pair[0]) > low(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert!(low("6s4d3c2hAh") < low("5s4d3c2hAh"));
+        assert!(low("6s5d3c2hAh") < low("6s4d3c2hAh"));
+        assert!(low("AsAd9c8h7h") > low("2s2dKcQhJh"));
+    }
+
+    #[test]
+    fn test_eight_or_better() {
+        let low = |abbr| low_ranker().eight_or_better5(cards(abbr));
+
+        assert_eq!(low("8s7d6c5h4h"), LowRank::MIN_EIGHT_OR_BETTER);
+        assert_eq!(low("9s4d3c2hAh"), LowRank::NO_LOW);
+        assert_eq!(low("8s8d3c2hAh"), LowRank::NO_LOW);
+        assert!(low("8s5d4c3h2h").is_low());
+
+        let qualifying = (0..POW_13_5)
+            .map(|hash5| low_ranker().ace_to_five5[hash5])
+            .filter(|low_rank| *low_rank >= LowRank::MIN_EIGHT_OR_BETTER)
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(qualifying.len(), 56);
+
+        let seven = low_ranker().eight_or_better7(cards("KsKdAc2h3h4c9s"));
+        assert_eq!(seven, LowRank::NO_LOW);
+        let seven = low_ranker().eight_or_better7(cards("KsKdAc2h3h4c8s"));
+        assert_eq!(seven, low("8s4d3c2hAh"));
+    }
+
+    #[test]
+    fn test_omaha_eight_or_better() {
+        let low = |hole: &str, board: &str| {
+            low_ranker()
+                .omaha_eight_or_better(OmahaHand::parse(hole).unwrap().cards(), cards(board))
+        };
+
+        assert_eq!(
+            low("As2sKdKc", "3c4d8hKs9c"),
+            low_ranker().ace_to_five5(cards("8h4d3c2sAs"))
+        );
+        // two board cards are not enough, whatever the hole cards
+        assert_eq!(low("As2s3d4c", "5c8dKhKs9c"), LowRank::NO_LOW);
+        // neither are three hole cards: A-2-3 with 6-7 would be a seven low
+        assert_eq!(
+            low("As2s3dKc", "6c7d8hKs9c"),
+            low_ranker().ace_to_five5(cards("8h7d6c2sAs"))
+        );
+    }
+
+    #[test]
+    fn test_deuce_to_seven() {
+        let low = |abbr| low_ranker().deuce_to_seven5(cards(abbr));
+        let best = LowRank::from_value(LowRank::NUM_DEUCE_TO_SEVEN.value() - 1);
+
+        assert_eq!(low("7s5d4c3h2h"), best);
+        assert!(low("7s6d4c3h2h") < best);
+        assert!(low("8s5d4c3h2h") < low("7s6d5c4h2h"));
+        // aces are high and there is no wheel
+        assert!(low("As5d4c3h2h") < low("Ks5d4c3h2h"));
+        assert!(low("As5d4c3h2h") > low("2s2d3c4h5h"));
+        // straights and flushes count against the hand
+        assert!(low("6s5d4c3h2h") < low("AsKdQcJh9h"));
+        assert!(low("7s5s4s3s2s") < low("AsKdQcJh9h"));
+        assert!(low("7s5s4s3s2s") > low("2s2d2c3h3h"));
+        // only the nine straight flushes from 6-high up are worse, A-2-3-4-5 suited is a flush
+        assert_eq!(low("AsAdAcAhKs"), LowRank::from_value(9));
+        assert!(low("As5s4s3s2s") > low("2s2d2c3h3h"));
+    }
+
+    #[test]
+    fn test_seven_cards() {
+        let low_ranker = low_ranker();
+        let deck = (0..52).map(Card::from_value).collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(23);
+
+        for _ in 0..5000 {
+            let mut deck = deck.clone();
+            deck.shuffle(&mut rng);
+            let seven: [Card; 7] = deck[..7].try_into().unwrap();
+
+            let mut ace_to_five = LowRank::NO_LOW;
+            let mut deuce_to_seven = LowRank::NO_LOW;
+            for skip0 in 0..7 {
+                for skip1 in skip0 + 1..7 {
+                    let mut five = [seven[0]; 5];
+                    for (pos, i) in (0..7).filter(|&i| i != skip0 && i != skip1).enumerate() {
+                        five[pos] = seven[i];
+                    }
+                    ace_to_five = max(ace_to_five, low_ranker.ace_to_five5(five));
+                    deuce_to_seven = max(deuce_to_seven, low_ranker.deuce_to_seven5(five));
+                }
+            }
+
+            assert_eq!(low_ranker.ace_to_five7(seven), ace_to_five);
+            assert_eq!(low_ranker.deuce_to_seven7(seven), deuce_to_seven);
+            assert_eq!(
+                low_ranker.eight_or_better7(seven),
+                eight_or_better(ace_to_five)
+            );
+        }
+    }
+}
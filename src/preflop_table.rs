@@ -0,0 +1,226 @@
+use super::equitizer::normalize_preflop;
+use super::equitizer::Equitizer;
+use super::types::Card;
+use super::types::Combo;
+use super::types::EquityResult;
+use super::types::StackedError;
+use crate::format_stacked_err;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Exact preflop results for every suit pattern `Equitizer::hand_vs_hand` normalises to.
+///
+/// File layout, little-endian: the magic `HCPF`, a `u32` format version and a `u32` entry
+/// count, then one 16-byte entry per matchup: the four normalised card values as `u8`
+/// (hero, hero, villain, villain) followed by wins, ties and losses as `u32`.
+pub struct PreflopTable {
+    results: HashMap<(Card, Card, Card, Card), EquityResult>,
+}
+
+impl PreflopTable {
+    /// Where `examples/gen_preflop_table.rs` writes the table that [`PreflopTable::embedded`]
+    /// builds into the crate.
+    pub const DEFAULT_FILENAME: &'static str = "src/preflop_table.bin";
+
+    const MAGIC: &'static [u8; 4] = b"HCPF";
+    const VERSION: u32 = 1;
+    const HEADER_LEN: usize = 12;
+    const ENTRY_LEN: usize = 16;
+
+    pub fn new() -> Self {
+        Self {
+            results: HashMap::new(),
+        }
+    }
+
+    /// The complete table shipped with the crate, see [`PreflopTable::DEFAULT_FILENAME`].
+    pub fn embedded() -> Self {
+        static BYTES: &[u8] = include_bytes!("preflop_table.bin");
+        Self::from_bytes(BYTES).expect("embedded preflop table")
+    }
+
+    /// Every distinct normalised (hero, villain) pair, in a stable order.
+    pub fn canonical_matchups() -> Vec<(Combo, Combo)> {
+        let combos = (0..52)
+            .flat_map(|c1| (0..c1).map(move |c2| (c1, c2)))
+            .map(|(c1, c2)| Combo::new(Card::from_value(c1), Card::from_value(c2)))
+            .collect::<Vec<_>>();
+
+        let mut matchups = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+
+        for &hero in &combos {
+            for &villain in &combos {
+                if hero.intersects(&villain) {
+                    continue;
+                }
+
+                let (hero, villain) = normalize_preflop(hero, villain);
+                if seen.insert((hero.0, hero.1, villain.0, villain.1)) {
+                    matchups.push((hero, villain));
+                }
+            }
+        }
+
+        matchups
+    }
+
+    /// Enumerates every canonical matchup with `equitizer`, reusing the mirrored matchup when
+    /// it has already been computed. `progress` is called with (done, total) after each one.
+    pub fn generate(equitizer: &Equitizer, mut progress: impl FnMut(usize, usize)) -> Self {
+        let matchups = Self::canonical_matchups();
+        let mut table = Self::new();
+
+        for (i, &(hero, villain)) in matchups.iter().enumerate() {
+            let (mirrored_hero, mirrored_villain) = normalize_preflop(villain, hero);
+            let res = match table.get(&(
+                mirrored_hero.0,
+                mirrored_hero.1,
+                mirrored_villain.0,
+                mirrored_villain.1,
+            )) {
+                Some(mirrored_res) => EquityResult {
+                    wins: mirrored_res.losses,
+                    ties: mirrored_res.ties,
+                    losses: mirrored_res.wins,
+                    total: mirrored_res.total,
+                },
+                None => equitizer.calc_combo_vs_combo(hero, villain),
+            };
+
+            table
+                .results
+                .insert((hero.0, hero.1, villain.0, villain.1), res);
+            progress(i + 1, matchups.len());
+        }
+
+        table
+    }
+
+    /// Looks up a key made of the cards returned by the preflop normalisation.
+    pub fn get(&self, key: &(Card, Card, Card, Card)) -> Option<EquityResult> {
+        self.results.get(key).copied()
+    }
+
+    pub fn insert(&mut self, hero: Combo, villain: Combo, res: EquityResult) {
+        let (hero, villain) = normalize_preflop(hero, villain);
+        self.results
+            .insert((hero.0, hero.1, villain.0, villain.1), res);
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn save_to(&self, filename: &str) -> Result<(), StackedError> {
+        let mut keys = self.results.keys().copied().collect::<Vec<_>>();
+        keys.sort();
+
+        let mut bytes = Vec::with_capacity(Self::HEADER_LEN + keys.len() * Self::ENTRY_LEN);
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&Self::VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+
+        for key in keys {
+            let res = self.results[&key];
+            for card in [key.0, key.1, key.2, key.3] {
+                bytes.push(card.value() as u8);
+            }
+            for count in [res.wins, res.ties, res.losses] {
+                bytes.extend_from_slice(&(count as u32).to_le_bytes());
+            }
+        }
+
+        std::fs::File::create(filename)
+            .and_then(|mut file| file.write_all(&bytes))
+            .map_err(|e| StackedError::new(format!("failed to write {:?}: {}", filename, e)))
+    }
+
+    pub fn load_from(filename: &str) -> Result<Self, StackedError> {
+        let bytes = match std::fs::read(filename) {
+            Ok(bytes) => bytes,
+            Err(e) => return format_stacked_err!("failed to read {:?}: {}", filename, e),
+        };
+        Self::from_bytes(&bytes)
+            .map_err(|e| e.chain(format!("PreflopTable::load_from({:?})", filename)))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, StackedError> {
+        if bytes.len() < Self::HEADER_LEN || &bytes[0..4] != Self::MAGIC {
+            return format_stacked_err!("not a preflop table");
+        }
+
+        let read_u32 = |pos: usize| u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap());
+
+        let version = read_u32(4);
+        if version != Self::VERSION {
+            return format_stacked_err!("unsupported preflop table version: {}", version);
+        }
+
+        let num_entries = read_u32(8) as usize;
+        if bytes.len() != Self::HEADER_LEN + num_entries * Self::ENTRY_LEN {
+            return format_stacked_err!(
+                "preflop table size mismatch: {} bytes for {} entries",
+                bytes.len(),
+                num_entries
+            );
+        }
+
+        let mut results = HashMap::with_capacity(num_entries);
+        for i in 0..num_entries {
+            let pos = Self::HEADER_LEN + i * Self::ENTRY_LEN;
+            let entry = &bytes[pos..pos + Self::ENTRY_LEN];
+            if entry[0..4].iter().any(|&v| v >= 52) {
+                return format_stacked_err!("invalid card in preflop table entry {}", i);
+            }
+
+            let card = |j: usize| Card::from_value(entry[j] as i32);
+            let res = EquityResult::new(
+                read_u32(pos + 4) as u64,
+                read_u32(pos + 8) as u64,
+                read_u32(pos + 12) as u64,
+            );
+            results.insert((card(0), card(1), card(2), card(3)), res);
+        }
+
+        Ok(Self { results })
+    }
+}
+
+impl Default for PreflopTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+
+    #[test]
+    fn test_save_and_load() {
+        let matchups = PreflopTable::canonical_matchups();
+        let mut table = PreflopTable::new();
+        for (i, &(hero, villain)) in matchups.iter().take(100).enumerate() {
+            table.insert(hero, villain, EquityResult::new(i as u64, 2, 3));
+        }
+
+        let temp_path = TempPath::new("preflop_table_save_and_load.bin");
+        let filename = temp_path.as_str();
+        table.save_to(filename).unwrap();
+        let loaded = PreflopTable::load_from(filename).unwrap();
+
+        assert_eq!(loaded.len(), 100);
+        for (i, &(hero, villain)) in matchups.iter().take(100).enumerate() {
+            let key = (hero.0, hero.1, villain.0, villain.1);
+            assert_eq!(loaded.get(&key), Some(EquityResult::new(i as u64, 2, 3)));
+        }
+
+        assert!(PreflopTable::from_bytes(b"HCPF\x02\x00\x00\x00\x00\x00\x00\x00").is_err());
+    }
+}
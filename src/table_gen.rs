@@ -0,0 +1,210 @@
+// Generation of the HandRanker lookup tables. build.rs compiles this file on its own through
+// `#[path]` for the `embedded` feature, so it may only depend on std.
+
+pub const NUM_HAND_RANKS: usize = 7462;
+/// Hand ranks of short-deck hold'em, which only uses the ranks 6 to A.
+pub const NUM_SHORT_DECK_HAND_RANKS: usize = 1404;
+pub const NUM_SUITED: usize = 1 << 13;
+pub const NUM_OFFSUITED5: usize = 13 * 13 * 13 * 13 * 13;
+pub const ERROR: i16 = -1;
+
+pub const TABLE_MAGIC: &[u8; 4] = b"HCHR";
+pub const TABLE_VERSION: u32 = 1;
+/// Written little-endian, so a file from a big-endian writer reads back as 0x04030201.
+pub const TABLE_BYTE_ORDER_MARK: u32 = 0x01020304;
+pub const TABLE_ENTRY_SIZE: u32 = 2;
+pub const TABLE_HEADER_LEN: usize = 28;
+
+const VALUE_2: i32 = 0;
+const VALUE_6: i32 = 4;
+const VALUE_A: i32 = 12;
+
+/// Rules of a high-hand ordering.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ranking {
+    Standard,
+    /// Ranks 6 to A only, A-6-7-8-9 is the lowest straight and a flush beats a full house.
+    ShortDeck,
+    /// Standard, except that A-2-3-4-5 is no straight but ace high, as in deuce-to-seven
+    /// lowball.
+    NoWheel,
+}
+
+/// One five-card hand pattern per hand rank, weakest first: the ranks in order of
+/// significance (pairs and sets before kickers, a wheel as 5-4-3-2-A, or 9-8-7-6-A in short
+/// deck) and whether the five cards share a suit.
+pub fn hand_patterns(ranking: Ranking) -> Vec<([i32; 5], bool)> {
+    let short_deck = ranking == Ranking::ShortDeck;
+    let has_wheel = ranking != Ranking::NoWheel;
+    let (lowest, num_hand_ranks) = if short_deck {
+        (VALUE_6, NUM_SHORT_DECK_HAND_RANKS)
+    } else {
+        (VALUE_2, NUM_HAND_RANKS)
+    };
+    let mut patterns = Vec::with_capacity(num_hand_ranks);
+
+    let mut no_pairs = Vec::new();
+    for r4 in lowest..=VALUE_A {
+        for r3 in lowest..r4 {
+            for r2 in lowest..r3 {
+                for r1 in lowest..r2 {
+                    for r0 in lowest..r1 {
+                        let is_straight = r3 - r0 == 3
+                            && (r4 == r3 + 1 || (has_wheel && r4 == VALUE_A && r0 == lowest));
+                        if !is_straight {
+                            no_pairs.push([r4, r3, r2, r1, r0]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut straights = Vec::new();
+    if has_wheel {
+        straights.push([lowest + 3, lowest + 2, lowest + 1, lowest, VALUE_A]);
+    }
+    for r in lowest + 4..=VALUE_A {
+        straights.push([r, r - 1, r - 2, r - 3, r - 4]);
+    }
+
+    // high card
+    patterns.extend(no_pairs.iter().map(|&ranks| (ranks, false)));
+
+    // one pair
+    for rr in lowest..=VALUE_A {
+        for r2 in (lowest..=VALUE_A).filter(|&r| r != rr) {
+            for r1 in (lowest..r2).filter(|&r| r != rr) {
+                for r0 in (lowest..r1).filter(|&r| r != rr) {
+                    patterns.push(([rr, rr, r2, r1, r0], false));
+                }
+            }
+        }
+    }
+
+    // two pair
+    for rr1 in lowest..=VALUE_A {
+        for rr0 in lowest..rr1 {
+            for r in (lowest..=VALUE_A).filter(|&r| r != rr1 && r != rr0) {
+                patterns.push(([rr1, rr1, rr0, rr0, r], false));
+            }
+        }
+    }
+
+    // trips
+    for rrr in lowest..=VALUE_A {
+        for r2 in (lowest..=VALUE_A).filter(|&r| r != rrr) {
+            for r1 in (lowest..r2).filter(|&r| r != rrr) {
+                patterns.push(([rrr, rrr, rrr, r2, r1], false));
+            }
+        }
+    }
+
+    patterns.extend(straights.iter().map(|&ranks| (ranks, false)));
+
+    let mut full_houses = Vec::new();
+    for rrr in lowest..=VALUE_A {
+        for rr in (lowest..=VALUE_A).filter(|&r| r != rrr) {
+            full_houses.push(([rrr, rrr, rrr, rr, rr], false));
+        }
+    }
+    let flushes = no_pairs.iter().map(|&ranks| (ranks, true));
+    if short_deck {
+        patterns.extend(full_houses);
+        patterns.extend(flushes);
+    } else {
+        patterns.extend(flushes);
+        patterns.extend(full_houses);
+    }
+
+    // quads
+    for rrrr in lowest..=VALUE_A {
+        for r in (lowest..=VALUE_A).filter(|&r| r != rrrr) {
+            patterns.push(([rrrr, rrrr, rrrr, rrrr, r], false));
+        }
+    }
+
+    patterns.extend(straights.iter().map(|&ranks| (ranks, true)));
+
+    assert_eq!(patterns.len(), num_hand_ranks);
+    patterns
+}
+
+pub struct Tables {
+    /// Best flush or straight flush within a 13-bit rank mask of one suit.
+    pub suited: Vec<i16>,
+    /// Hand rank of five ranks in the given order, base-13 indexed.
+    pub offsuited5: Vec<i16>,
+}
+
+/// Tables of [`hand_patterns`]; in short deck, hands with a rank below 6 are `ERROR`.
+pub fn generate(ranking: Ranking) -> Tables {
+    let mut suited = vec![ERROR; NUM_SUITED];
+    let mut sorted_offsuited = std::collections::HashMap::new();
+
+    for (hand_rank, (ranks, is_flush)) in hand_patterns(ranking).into_iter().enumerate() {
+        if is_flush {
+            let mask = ranks.iter().fold(0, |mask, r| mask | (1 << r));
+            suited[mask] = hand_rank as i16;
+        } else {
+            let mut sorted = ranks;
+            sorted.sort();
+            sorted_offsuited.insert(sorted, hand_rank as i16);
+        }
+    }
+
+    // a mask of 6 or 7 ranks gets the best flush among its subsets
+    for mask in 0..NUM_SUITED {
+        for i in 0..13 {
+            if mask & (1 << i) != 0 {
+                suited[mask] = suited[mask].max(suited[mask ^ (1 << i)]);
+            }
+        }
+    }
+
+    let mut offsuited5 = vec![ERROR; NUM_OFFSUITED5];
+    for (hash5, hand_rank) in offsuited5.iter_mut().enumerate() {
+        let mut ranks = [0; 5];
+        let mut rest = hash5 as i32;
+        for r in ranks.iter_mut().rev() {
+            *r = rest % 13;
+            rest /= 13;
+        }
+        ranks.sort();
+
+        if let Some(&value) = sorted_offsuited.get(&ranks) {
+            *hand_rank = value;
+        }
+    }
+
+    Tables { suited, offsuited5 }
+}
+
+/// Table file layout, little-endian: the magic `HCHR`, a `u32` format version, a `u32` byte
+/// order mark, the `u32` entry size (2) and `u32` entry count, a `u64` FNV-1a checksum of the
+/// payload, then one `i16` hand rank per entry.
+pub fn encode_table(table: &[i16]) -> Vec<u8> {
+    let payload = table
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<_>>();
+
+    let mut bytes = Vec::with_capacity(TABLE_HEADER_LEN + payload.len());
+    bytes.extend_from_slice(TABLE_MAGIC);
+    bytes.extend_from_slice(&TABLE_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&TABLE_BYTE_ORDER_MARK.to_le_bytes());
+    bytes.extend_from_slice(&TABLE_ENTRY_SIZE.to_le_bytes());
+    bytes.extend_from_slice(&(table.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&fnv1a64(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    bytes
+}
+
+pub fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
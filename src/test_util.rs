@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+/// A path in the temp dir that is unique to the process and the test, removed on drop so it
+/// does not outlive a failing test.
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    pub fn new(name: &str) -> TempPath {
+        let filename = format!("hardcore_equitizer_{}_{}", std::process::id(), name);
+        TempPath(std::env::temp_dir().join(filename))
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        if self.0.is_dir() {
+            let _ = std::fs::remove_dir_all(&self.0);
+        } else {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+}
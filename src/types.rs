@@ -1,17 +1,38 @@
+mod best_hand;
 pub mod card;
 mod combo;
+mod equity_result;
+mod hand_category;
 pub mod hand_rank;
+mod hi_lo_equity_result;
+mod low_rank;
 mod mixed_range;
+mod monte_carlo;
+mod omaha_hand;
+mod omaha_range;
+mod partial_hand;
 mod pure_range;
 mod range;
 pub mod rank;
 mod stacked_error;
 mod suit;
 
+pub use best_hand::BestHand;
 pub use card::Card;
 pub use combo::Combo;
+pub use combo::WeightedCombo;
+pub use equity_result::ComboEquity;
+pub use equity_result::EquityResult;
+pub use hand_category::HandCategory;
 pub use hand_rank::HandRank;
+pub use hi_lo_equity_result::HiLoEquityResult;
+pub use low_rank::LowRank;
 pub use mixed_range::MixedRange;
+pub use monte_carlo::MonteCarloConfig;
+pub use monte_carlo::MonteCarloResult;
+pub use omaha_hand::OmahaHand;
+pub use omaha_range::OmahaRange;
+pub use partial_hand::PartialHand;
 pub use pure_range::PureRange;
 pub use range::Range;
 pub use rank::Rank;
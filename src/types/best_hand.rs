@@ -0,0 +1,12 @@
+use super::card::Card;
+use super::hand_rank::HandRank;
+
+/// The five cards that play out of a seven-card hand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BestHand {
+    pub hand_rank: HandRank,
+    /// Ordered like [`HandRank::ranks`], e.g. K K 7 7 A for Kings and Sevens with an Ace.
+    pub cards: [Card; 5],
+    /// The hole cards among `cards`, empty when the board plays.
+    pub hole_cards: Vec<Card>,
+}
@@ -14,13 +14,13 @@ pub struct Card {
 
 impl fmt::Debug for Card {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        write!(f, "{}", self)
     }
 }
 
-impl ToString for Card {
-    fn to_string(&self) -> String {
-        return format!("{}{}", self.rank().to_string(), self.suit().to_string());
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.rank(), self.suit())
     }
 }
 
@@ -64,6 +64,11 @@ impl Card {
         self.value
     }
 
+    /// The card's bit in a `u64` card mask.
+    pub fn mask(self) -> u64 {
+        1 << self.value
+    }
+
     pub fn from_rank_suit_value(rank_value: i32, suit_value: i32) -> Self {
         Self::from_value(rank_value << 2 | suit_value)
     }
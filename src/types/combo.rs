@@ -1,7 +1,7 @@
 use super::card::Card;
 use std::cmp::Ordering;
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Debug, Hash, Eq, PartialOrd, Ord)]
 pub struct Combo(pub Card, pub Card);
 
 impl Combo {
@@ -23,6 +23,14 @@ impl Combo {
     pub fn intersects(&self, other: &Self) -> bool {
         self.0 == other.0 || self.0 == other.1 || self.1 == other.0 || self.1 == other.1
     }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.0 == card || self.1 == card
+    }
+
+    pub fn intersects_cards(&self, cards: &[Card]) -> bool {
+        cards.iter().any(|&c| self.contains(c))
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -0,0 +1,58 @@
+use super::combo::Combo;
+
+/// Showdown outcomes of a matchup from the hero's point of view.
+///
+/// For a single combo against a single combo the fields count runouts. Range queries add up
+/// the per-matchup frequencies scaled by the matchup weight, so `total` is the sum of weights.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EquityResult {
+    pub wins: f64,
+    pub ties: f64,
+    pub losses: f64,
+    pub total: f64,
+}
+
+impl EquityResult {
+    pub fn new(wins: u64, ties: u64, losses: u64) -> Self {
+        Self {
+            wins: wins as f64,
+            ties: ties as f64,
+            losses: losses as f64,
+            total: (wins + ties + losses) as f64,
+        }
+    }
+
+    pub fn equity(&self) -> f64 {
+        (self.wins + self.ties * 0.5) / self.total
+    }
+
+    pub fn win_freq(&self) -> f64 {
+        self.wins / self.total
+    }
+
+    pub fn tie_freq(&self) -> f64 {
+        self.ties / self.total
+    }
+
+    pub fn lose_freq(&self) -> f64 {
+        self.losses / self.total
+    }
+
+    /// Adds `other` so that it accounts for `weight` of the total.
+    pub fn add_weighted(&mut self, other: &Self, weight: f64) {
+        self.wins += other.win_freq() * weight;
+        self.ties += other.tie_freq() * weight;
+        self.losses += other.lose_freq() * weight;
+        self.total += weight;
+    }
+}
+
+/// Equity of one hero combo against a whole villain range.
+#[derive(Clone, Copy, Debug)]
+pub struct ComboEquity {
+    pub combo: Combo,
+    /// Combo weight times the villain weight left after card removal, i.e. the share of this
+    /// combo in the range-vs-range aggregate.
+    pub weight: f64,
+    pub result: EquityResult,
+}
@@ -0,0 +1,73 @@
+use super::hand_rank::HandRank;
+use std::fmt;
+
+/// Kind of five-card hand, in increasing order of strength.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum HandCategory {
+    HighCard,
+    Pair,
+    TwoPair,
+    Trips,
+    Straight,
+    Flush,
+    FullHouse,
+    Quads,
+    StraightFlush,
+}
+
+impl fmt::Display for HandCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl HandCategory {
+    pub const ALL: [Self; 9] = [
+        Self::HighCard,
+        Self::Pair,
+        Self::TwoPair,
+        Self::Trips,
+        Self::Straight,
+        Self::Flush,
+        Self::FullHouse,
+        Self::Quads,
+        Self::StraightFlush,
+    ];
+
+    /// Weakest hand of the category.
+    pub fn min_rank(self) -> HandRank {
+        match self {
+            Self::HighCard => HandRank::MIN_HIGH_CARD,
+            Self::Pair => HandRank::MIN_PAIR,
+            Self::TwoPair => HandRank::MIN_TWO_PAIR,
+            Self::Trips => HandRank::MIN_TRIPS,
+            Self::Straight => HandRank::MIN_STRAIGHT,
+            Self::Flush => HandRank::MIN_FLUSH,
+            Self::FullHouse => HandRank::MIN_FULL_HOUSE,
+            Self::Quads => HandRank::MIN_QUADS,
+            Self::StraightFlush => HandRank::MIN_STRAIGHT_FLUSH,
+        }
+    }
+
+    /// Strongest hand of the category.
+    pub fn max_rank(self) -> HandRank {
+        match Self::ALL.get(self as usize + 1) {
+            Some(next) => HandRank::from_value(next.min_rank().value() - 1),
+            None => HandRank::from_value(HandRank::NUM.value() - 1),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::HighCard => "High card",
+            Self::Pair => "Pair",
+            Self::TwoPair => "Two pair",
+            Self::Trips => "Three of a kind",
+            Self::Straight => "Straight",
+            Self::Flush => "Flush",
+            Self::FullHouse => "Full house",
+            Self::Quads => "Four of a kind",
+            Self::StraightFlush => "Straight flush",
+        }
+    }
+}
@@ -1,4 +1,8 @@
+use super::hand_category::HandCategory;
+use super::rank::Rank;
+use crate::table_gen;
 use std::ops::Add;
+use std::sync::OnceLock;
 
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Copy)]
 pub struct HandRank {
@@ -17,6 +21,17 @@ impl HandRank {
     pub const ERROR: Self = Self { value: -1 };
     pub const NUM: Self = Self { value: 7462 };
 
+    // weakest hand of each category, in the order `table_gen::hand_patterns` lists them
+    pub const MIN_HIGH_CARD: Self = Self { value: 0 };
+    pub const MIN_PAIR: Self = Self { value: 1277 };
+    pub const MIN_TWO_PAIR: Self = Self { value: 4137 };
+    pub const MIN_TRIPS: Self = Self { value: 4995 };
+    pub const MIN_STRAIGHT: Self = Self { value: 5853 };
+    pub const MIN_FLUSH: Self = Self { value: 5863 };
+    pub const MIN_FULL_HOUSE: Self = Self { value: 7140 };
+    pub const MIN_QUADS: Self = Self { value: 7296 };
+    pub const MIN_STRAIGHT_FLUSH: Self = Self { value: 7452 };
+
     pub fn from_value(value: i32) -> Self {
         Self { value }
     }
@@ -28,4 +43,147 @@ impl HandRank {
     pub fn inc(&mut self) {
         self.value += 1;
     }
+
+    pub fn is_valid(&self) -> bool {
+        (0..Self::NUM.value).contains(&self.value)
+    }
+
+    pub fn category(&self) -> HandCategory {
+        if !self.is_valid() {
+            panic!("invalid hand rank: {}", self.value);
+        }
+
+        *HandCategory::ALL
+            .iter()
+            .rev()
+            .find(|category| category.min_rank() <= *self)
+            .unwrap()
+    }
+
+    /// Ranks of the five cards, most significant first: the pairs or sets before the kickers,
+    /// and a wheel as 5-4-3-2-A.
+    pub fn ranks(&self) -> [Rank; 5] {
+        if !self.is_valid() {
+            panic!("invalid hand rank: {}", self.value);
+        }
+
+        rank_values()[self.value as usize].map(Rank::from_value)
+    }
+
+    /// "Two pair, Kings and Sevens, Ace kicker" and the like.
+    pub fn description(&self) -> String {
+        let category = self.category();
+        let [r0, r1, r2, r3, r4] = self.ranks();
+
+        let detail = match category {
+            HandCategory::HighCard => format!(
+                "{}, {} {} {} {} kickers",
+                r0.name(),
+                r1.name(),
+                r2.name(),
+                r3.name(),
+                r4.name()
+            ),
+            HandCategory::Pair => format!(
+                "{}, {} {} {} kickers",
+                r0.plural_name(),
+                r2.name(),
+                r3.name(),
+                r4.name()
+            ),
+            HandCategory::TwoPair => format!(
+                "{} and {}, {} kicker",
+                r0.plural_name(),
+                r2.plural_name(),
+                r4.name()
+            ),
+            HandCategory::Trips => {
+                format!("{}, {} {} kickers", r0.plural_name(), r3.name(), r4.name())
+            }
+            HandCategory::Straight | HandCategory::StraightFlush => {
+                format!("{} high", r0.name())
+            }
+            HandCategory::Flush => format!(
+                "{} {} {} {} {}",
+                r0.name(),
+                r1.name(),
+                r2.name(),
+                r3.name(),
+                r4.name()
+            ),
+            HandCategory::FullHouse => {
+                format!("{} full of {}", r0.plural_name(), r3.plural_name())
+            }
+            HandCategory::Quads => format!("{}, {} kicker", r0.plural_name(), r4.name()),
+        };
+
+        format!("{}, {}", category, detail)
+    }
+}
+
+/// Rank values of every hand rank, in the order of [`table_gen::hand_patterns`].
+fn rank_values() -> &'static [[i32; 5]] {
+    static RANK_VALUES: OnceLock<Vec<[i32; 5]>> = OnceLock::new();
+
+    RANK_VALUES.get_or_init(|| {
+        table_gen::hand_patterns(table_gen::Ranking::Standard)
+            .into_iter()
+            .map(|(ranks, _)| ranks)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category() {
+        assert_eq!(rank_values().len(), HandRank::NUM.value() as usize);
+
+        for category in HandCategory::ALL {
+            assert_eq!(category.min_rank().category(), category);
+            assert_eq!(category.max_rank().category(), category);
+        }
+        assert_eq!(
+            HandCategory::StraightFlush.max_rank(),
+            HandRank::from_value(7461)
+        );
+    }
+
+    #[test]
+    fn test_description() {
+        let description = |value| HandRank::from_value(value).description();
+
+        assert_eq!(
+            description(0),
+            "High card, Seven, Five Four Three Two kickers"
+        );
+        assert_eq!(description(1277), "Pair, Twos, Five Four Three kickers");
+        assert_eq!(description(4136), "Pair, Aces, King Queen Jack kickers");
+        assert_eq!(description(4137), "Two pair, Threes and Twos, Four kicker");
+        assert_eq!(
+            description(5852),
+            "Three of a kind, Aces, King Queen kickers"
+        );
+        assert_eq!(description(5853), "Straight, Five high");
+        assert_eq!(description(5862), "Straight, Ace high");
+        assert_eq!(description(7139), "Flush, Ace King Queen Jack Nine");
+        assert_eq!(description(7140), "Full house, Twos full of Threes");
+        assert_eq!(description(7451), "Four of a kind, Aces, King kicker");
+        assert_eq!(description(7461), "Straight flush, Ace high");
+
+        let kings_and_sevens = rank_values()
+            .iter()
+            .position(|&r| r == [11, 11, 5, 5, 12])
+            .unwrap();
+        assert_eq!(
+            description(kings_and_sevens as i32),
+            "Two pair, Kings and Sevens, Ace kicker"
+        );
+        assert_eq!(
+            HandRank::from_value(kings_and_sevens as i32).category(),
+            HandCategory::TwoPair
+        );
+    }
 }
@@ -0,0 +1,52 @@
+/// Split-pot outcomes of a hi-lo matchup from the hero's point of view, in pots.
+///
+/// A pot with a qualifying low splits into a high and a low half, and a tied half splits
+/// again, so getting quartered counts as a quarter pot. Without a qualifying low the high hand
+/// takes the whole pot. As with [`crate::EquityResult`], range queries add up frequencies
+/// scaled by the matchup weight.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HiLoEquityResult {
+    /// Pots won with the high hand.
+    pub high: f64,
+    /// Pots won with the low hand.
+    pub low: f64,
+    /// Runouts where the hero takes the whole pot alone.
+    pub scoops: f64,
+    pub total: f64,
+}
+
+impl HiLoEquityResult {
+    /// Pot shares are given in quarter pots, the unit of a heads-up hi-lo split.
+    pub fn new(high_quarters: u64, low_quarters: u64, scoops: u64, total: u64) -> Self {
+        Self {
+            high: high_quarters as f64 / 4.0,
+            low: low_quarters as f64 / 4.0,
+            scoops: scoops as f64,
+            total: total as f64,
+        }
+    }
+
+    pub fn equity(&self) -> f64 {
+        (self.high + self.low) / self.total
+    }
+
+    pub fn high_share(&self) -> f64 {
+        self.high / self.total
+    }
+
+    pub fn low_share(&self) -> f64 {
+        self.low / self.total
+    }
+
+    pub fn scoop_freq(&self) -> f64 {
+        self.scoops / self.total
+    }
+
+    /// Adds `other` so that it accounts for `weight` of the total.
+    pub fn add_weighted(&mut self, other: &Self, weight: f64) {
+        self.high += other.high_share() * weight;
+        self.low += other.low_share() * weight;
+        self.scoops += other.scoop_freq() * weight;
+        self.total += weight;
+    }
+}
@@ -0,0 +1,28 @@
+/// Strength of a lowball hand from [`crate::LowRanker`]. Like [`crate::HandRank`], a higher
+/// value is a better hand, so lows compare and `max` the same way. Ace-to-five and
+/// deuce-to-seven lows are numbered separately and only compare within their game.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Copy, Hash)]
+pub struct LowRank {
+    value: i32,
+}
+
+impl LowRank {
+    /// Below every low: an invalid hand, or one that misses the eight-or-better qualifier.
+    pub const NO_LOW: Self = Self { value: -1 };
+    pub const NUM_ACE_TO_FIVE: Self = Self { value: 6175 };
+    pub const NUM_DEUCE_TO_SEVEN: Self = Self { value: 7462 };
+    /// 8-7-6-5-4, the worst ace-to-five low that qualifies for eight-or-better.
+    pub const MIN_EIGHT_OR_BETTER: Self = Self { value: 6119 };
+
+    pub fn from_value(value: i32) -> Self {
+        Self { value }
+    }
+
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    pub fn is_low(&self) -> bool {
+        *self != Self::NO_LOW
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+/// Stopping rules for the sampling queries of `Equitizer`.
+///
+/// Sampling runs in batches of `batch_size` and stops after the first batch that reaches
+/// `max_samples`, brings the standard error down to `target_std_err` or exceeds `time_budget`.
+#[derive(Clone, Debug)]
+pub struct MonteCarloConfig {
+    pub max_samples: usize,
+    pub target_std_err: Option<f64>,
+    pub time_budget: Option<Duration>,
+    pub batch_size: usize,
+}
+
+impl Default for MonteCarloConfig {
+    fn default() -> Self {
+        Self {
+            max_samples: 1_000_000,
+            target_std_err: None,
+            time_budget: None,
+            batch_size: 1_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MonteCarloResult {
+    pub equity: f64,
+    pub std_err: f64,
+    pub num_samples: usize,
+}
+
+impl MonteCarloResult {
+    /// Builds the result from the sum and the sum of squares of the per-sample pot shares.
+    pub fn from_sums(sum: f64, sum_sq: f64, num_samples: usize) -> Self {
+        let n = num_samples as f64;
+        let equity = sum / n;
+        let variance = (sum_sq / n - equity * equity).max(0.0);
+
+        Self {
+            equity,
+            std_err: (variance / n).sqrt(),
+            num_samples,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use super::card::Card;
+use super::stacked_error::StackedError;
+use crate::format_stacked_err;
+use std::fmt;
+
+/// Hole cards of Pot-Limit Omaha: four for PLO4, five for PLO5. The cards are sorted in
+/// descending order, like [`crate::Combo::new`] does.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+pub struct OmahaHand {
+    cards: [Card; 5],
+    num_cards: usize,
+}
+
+impl fmt::Display for OmahaHand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for card in self.cards() {
+            write!(f, "{}", card)?;
+        }
+        Ok(())
+    }
+}
+
+impl OmahaHand {
+    pub const MIN_CARDS: usize = 4;
+    pub const MAX_CARDS: usize = 5;
+
+    pub fn new(cards: &[Card]) -> Result<Self, StackedError> {
+        if !(Self::MIN_CARDS..=Self::MAX_CARDS).contains(&cards.len()) {
+            return format_stacked_err!("an Omaha hand has 4 or 5 cards, got {:?}", cards);
+        }
+
+        let mut sorted = [Card::from_value(0); 5];
+        sorted[..cards.len()].copy_from_slice(cards);
+        sorted[..cards.len()].sort_by(|a, b| b.cmp(a));
+        if sorted[..cards.len()].windows(2).any(|w| w[0] == w[1]) {
+            return format_stacked_err!("duplicate card in Omaha hand {:?}", cards);
+        }
+
+        Ok(Self {
+            cards: sorted,
+            num_cards: cards.len(),
+        })
+    }
+
+    /// "AsKsQhJh" and the like.
+    pub fn parse(abbr: &str) -> Result<Self, StackedError> {
+        if !abbr.is_ascii() || abbr.len() % 2 != 0 {
+            return format_stacked_err!("invalid Omaha hand: {:?}", abbr);
+        }
+
+        let cards = (0..abbr.len())
+            .step_by(2)
+            .map(|i| Card::parse(&abbr[i..i + 2]))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.chain(format!("invalid Omaha hand: {:?}", abbr)))?;
+
+        Self::new(&cards)
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.cards[..self.num_cards]
+    }
+
+    pub fn num_cards(&self) -> usize {
+        self.num_cards
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.cards().contains(&card)
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.mask() & other.mask() != 0
+    }
+
+    pub fn intersects_cards(&self, cards: &[Card]) -> bool {
+        cards.iter().any(|&card| self.contains(card))
+    }
+
+    /// The hand as a `u64` card mask, see [`Card::mask`].
+    pub fn mask(&self) -> u64 {
+        self.cards().iter().fold(0, |mask, card| mask | card.mask())
+    }
+}
@@ -0,0 +1,152 @@
+use super::card::Card;
+use super::omaha_hand::OmahaHand;
+use super::rank::Rank;
+use super::stacked_error::StackedError;
+use crate::format_stacked_err;
+
+/// A set of Omaha hands, e.g. "AAKKds,AsKsQhJh".
+///
+/// A token is either explicit cards ("AsKsQhJh") or 4 or 5 ranks ("AAKK", "AKQJT") for every
+/// choice of suits, optionally restricted by the number of suits held at least twice:
+/// "ds" (double suited) two, "ss" (single suited) one, "r" (rainbow) none.
+pub struct OmahaRange {
+    pub hands: Vec<OmahaHand>,
+}
+
+impl From<&str> for OmahaRange {
+    fn from(desc: &str) -> Self {
+        Self::parse(desc).unwrap()
+    }
+}
+
+impl From<&String> for OmahaRange {
+    fn from(desc: &String) -> Self {
+        Self::parse(desc).unwrap()
+    }
+}
+
+impl OmahaRange {
+    pub fn parse(desc: &str) -> Result<Self, StackedError> {
+        let mut hands = Vec::new();
+
+        for token in desc.split(',') {
+            let token = token.trim();
+            if let Ok(hand) = OmahaHand::parse(token) {
+                hands.push(hand);
+                continue;
+            }
+
+            hands.extend(
+                Self::parse_ranks(token)
+                    .map_err(|e| e.chain(format!("invalid range description: {:?}", desc)))?,
+            );
+        }
+
+        Ok(Self { hands })
+    }
+
+    /// Every hand of the ranks of `token`, filtered by its suit suffix.
+    pub fn parse_ranks(token: &str) -> Result<Vec<OmahaHand>, StackedError> {
+        let (ranks, num_suited) = if let Some(ranks) = token.strip_suffix("ds") {
+            (ranks, Some(2))
+        } else if let Some(ranks) = token.strip_suffix("ss") {
+            (ranks, Some(1))
+        } else if let Some(ranks) = token.strip_suffix("r") {
+            (ranks, Some(0))
+        } else {
+            (token, None)
+        };
+
+        if !ranks.is_ascii()
+            || !(OmahaHand::MIN_CARDS..=OmahaHand::MAX_CARDS).contains(&ranks.len())
+        {
+            return format_stacked_err!("invalid range token: {:?}", token);
+        }
+
+        let mut rank_counts = [0; 13];
+        for i in 0..ranks.len() {
+            let rank = Rank::parse(&ranks[i..i + 1])
+                .map_err(|e| e.chain(format!("invalid range token: {:?}", token)))?;
+            rank_counts[rank.value as usize] += 1;
+        }
+        if rank_counts.iter().any(|&count| count > 4) {
+            return format_stacked_err!("invalid range token: {:?}", token);
+        }
+
+        let mut res = Vec::new();
+        let mut cards = Vec::with_capacity(ranks.len());
+        for_each_suit_choice(&rank_counts, 0, &mut cards, &mut |cards| {
+            let mut suit_counts = [0; 4];
+            for card in cards.iter() {
+                suit_counts[card.suit().as_usize()] += 1;
+            }
+            let suited = suit_counts.iter().filter(|&&count| count >= 2).count();
+
+            if num_suited.is_none_or(|num_suited| suited == num_suited) {
+                res.push(OmahaHand::new(cards).unwrap());
+            }
+        });
+
+        Ok(res)
+    }
+
+    pub fn contain_hand(&self, hand: &OmahaHand) -> bool {
+        self.hands.iter().any(|h| h == hand)
+    }
+}
+
+/// Calls `f` with every way to give `rank_counts[r]` distinct suits to each rank `r` from
+/// `rank_value` up.
+fn for_each_suit_choice(
+    rank_counts: &[usize; 13],
+    rank_value: usize,
+    cards: &mut Vec<Card>,
+    f: &mut impl FnMut(&[Card]),
+) {
+    if rank_value == rank_counts.len() {
+        f(cards);
+        return;
+    }
+
+    let count = rank_counts[rank_value];
+    for suits in 0..16_u32 {
+        if suits.count_ones() as usize != count {
+            continue;
+        }
+
+        let len = cards.len();
+        for suit_value in (0..4).filter(|s| suits & (1 << s) != 0) {
+            cards.push(Card::from_rank_suit_value(rank_value as i32, suit_value));
+        }
+        for_each_suit_choice(rank_counts, rank_value + 1, cards, f);
+        cards.truncate(len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let count = |desc: &str| OmahaRange::parse(desc).unwrap().hands.len();
+
+        assert_eq!(count("AAKK"), 36);
+        assert_eq!(count("AAKKds"), 6);
+        assert_eq!(count("AAKKr"), 6);
+        assert_eq!(count("AAKKss"), 24);
+        assert_eq!(count("AKQJ"), 256);
+        assert_eq!(count("AAAA"), 1);
+        assert_eq!(count("AKQJT"), 1024);
+        assert_eq!(count("AsKsQhJh, AAKKds"), 7);
+
+        let range = OmahaRange::from("AAKKds");
+        assert!(range.contain_hand(&OmahaHand::parse("AsKsAhKh").unwrap()));
+        assert!(!range.contain_hand(&OmahaHand::parse("AsKdAhKc").unwrap()));
+
+        assert!(OmahaRange::parse("AAAAA").is_err());
+        assert!(OmahaRange::parse("AKQ").is_err());
+        assert!(OmahaRange::parse("AKQJxs").is_err());
+        assert!(OmahaRange::parse("AsAsKhKd").is_err());
+    }
+}
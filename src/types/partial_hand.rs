@@ -0,0 +1,76 @@
+use super::card::Card;
+
+/// Cards collected so far for [`crate::HandRanker::evaluate_partial`], e.g. the hole cards
+/// before the board is dealt. Adding a card or joining two disjoint hands is a few bit
+/// operations, so an enumeration can keep the state of the fixed cards and only add the
+/// runout.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct PartialHand {
+    /// Bit `card.value()` per card, see [`Card::mask`].
+    pub(crate) mask: u64,
+    /// Number of cards of suit `s` in bits `3 * s..3 * s + 3`.
+    pub(crate) suit_cnt_hash: usize,
+    /// Bit `rank` per card of each suit.
+    pub(crate) suited_hashes: [usize; 4],
+}
+
+impl PartialHand {
+    pub const EMPTY: Self = Self {
+        mask: 0,
+        suit_cnt_hash: 0,
+        suited_hashes: [0; 4],
+    };
+
+    pub fn from_cards(cards: &[Card]) -> Self {
+        cards
+            .iter()
+            .fold(Self::EMPTY, |hand, &card| hand.with_card(card))
+    }
+
+    pub fn from_mask(mut mask: u64) -> Self {
+        let mut hand = Self::EMPTY;
+        while mask != 0 {
+            hand = hand.with_card(Card::from_value(mask.trailing_zeros() as i32));
+            mask &= mask - 1;
+        }
+        hand
+    }
+
+    /// Adds a card that is not in the hand yet.
+    pub fn with_card(self, card: Card) -> Self {
+        debug_assert!(!self.contains(card), "{} is already in the hand", card);
+
+        let suit = card.suit().as_usize();
+        let mut suited_hashes = self.suited_hashes;
+        suited_hashes[suit] |= 1 << card.rank().as_usize();
+
+        Self {
+            mask: self.mask | card.mask(),
+            suit_cnt_hash: self.suit_cnt_hash + (1 << (suit * 3)),
+            suited_hashes,
+        }
+    }
+
+    /// Joins two hands without common cards.
+    pub fn union(self, other: Self) -> Self {
+        debug_assert!(self.mask & other.mask == 0, "the hands share cards");
+
+        Self {
+            mask: self.mask | other.mask,
+            suit_cnt_hash: self.suit_cnt_hash + other.suit_cnt_hash,
+            suited_hashes: [0, 1, 2, 3].map(|s| self.suited_hashes[s] | other.suited_hashes[s]),
+        }
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.mask & card.mask() != 0
+    }
+
+    pub fn mask(&self) -> u64 {
+        self.mask
+    }
+
+    pub fn num_cards(&self) -> usize {
+        self.mask.count_ones() as usize
+    }
+}
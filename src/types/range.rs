@@ -5,3 +5,13 @@ pub trait Range {
     fn iter_weighted_combos(&self) -> impl Iterator<Item = WeightedCombo> + '_;
     fn iter_combos(&self) -> impl Iterator<Item = Combo> + '_;
 }
+
+impl Range for Vec<WeightedCombo> {
+    fn iter_weighted_combos(&self) -> impl Iterator<Item = WeightedCombo> + '_ {
+        self.iter().copied()
+    }
+
+    fn iter_combos(&self) -> impl Iterator<Item = Combo> + '_ {
+        self.iter().map(|wc| wc.combo)
+    }
+}
@@ -1,5 +1,6 @@
 use super::stacked_error::StackedError;
 use crate::format_stacked_err;
+use std::fmt;
 use std::ops::Add;
 use std::ops::Sub;
 
@@ -26,9 +27,9 @@ impl Sub<Rank> for Rank {
     }
 }
 
-impl ToString for Rank {
-    fn to_string(&self) -> String {
-        self.to_str().to_string()
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_str())
     }
 }
 
@@ -65,23 +66,51 @@ impl Rank {
         Ok(Self { value })
     }
 
-    pub fn to_str(&self) -> &str {
+    pub fn to_str(self) -> &'static str {
         match self.value {
-            0 => return "2",
-            1 => return "3",
-            2 => return "4",
-            3 => return "5",
-            4 => return "6",
-            5 => return "7",
-            6 => return "8",
-            7 => return "9",
-            8 => return "T",
-            9 => return "J",
-            10 => return "Q",
-            11 => return "K",
-            12 => return "A",
+            0 => "2",
+            1 => "3",
+            2 => "4",
+            3 => "5",
+            4 => "6",
+            5 => "7",
+            6 => "8",
+            7 => "9",
+            8 => "T",
+            9 => "J",
+            10 => "Q",
+            11 => "K",
+            12 => "A",
             _ => panic!("invalid rank: {}", self.value),
-        };
+        }
+    }
+
+    /// "Ace", "King", ... "Two".
+    pub fn name(self) -> &'static str {
+        match self.value {
+            0 => "Two",
+            1 => "Three",
+            2 => "Four",
+            3 => "Five",
+            4 => "Six",
+            5 => "Seven",
+            6 => "Eight",
+            7 => "Nine",
+            8 => "Ten",
+            9 => "Jack",
+            10 => "Queen",
+            11 => "King",
+            12 => "Ace",
+            _ => panic!("invalid rank: {}", self.value),
+        }
+    }
+
+    /// "Aces", "Kings", ... "Twos".
+    pub fn plural_name(self) -> String {
+        match self.value {
+            4 => "Sixes".to_string(),
+            _ => format!("{}s", self.name()),
+        }
     }
 
     pub fn as_usize(&self) -> usize {
@@ -1,20 +1,22 @@
 use super::stacked_error::StackedError;
 use crate::format_stacked_err;
+use std::fmt;
 
 #[derive(Clone, PartialEq)]
 pub struct Suit {
     pub value: i32,
 }
 
-impl ToString for Suit {
-    fn to_string(&self) -> String {
-        match self.value {
-            0 => return "c".to_string(),
-            1 => return "d".to_string(),
-            2 => return "h".to_string(),
-            3 => return "s".to_string(),
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let abbr = match self.value {
+            0 => "c",
+            1 => "d",
+            2 => "h",
+            3 => "s",
             _ => panic!("invalid suit"),
         };
+        write!(f, "{}", abbr)
     }
 }
 
@@ -25,19 +27,19 @@ impl Suit {
 
     pub fn parse(abbr: char) -> Result<Self, StackedError> {
         match abbr {
-            's' => return Ok(Self::SPADE),
-            'h' => return Ok(Self::HEART),
-            'd' => return Ok(Self::DIAMOND),
-            'c' => return Ok(Self::CLUB),
+            's' => Ok(Self::SPADE),
+            'h' => Ok(Self::HEART),
+            'd' => Ok(Self::DIAMOND),
+            'c' => Ok(Self::CLUB),
             _ => {
-                return format_stacked_err!(
+                format_stacked_err!(
                     "Suit::parse({}:{}) invalid suit: {:?}",
                     file!(),
                     line!(),
                     abbr
-                );
+                )
             }
-        };
+        }
     }
 
     pub fn from_i32(value: i32) -> Self {